@@ -1,4 +1,6 @@
+use std::path::PathBuf;
 use chromiumoxide::BrowserConfig;
+use chromiumoxide::handler::viewport::Viewport;
 use crate::PinterestLoginError;
 #[cfg(feature = "debug")]
 use log::{info, trace, debug};
@@ -15,11 +17,11 @@ use log::{info, trace, debug};
 /// struct MyBrowserConfigBuilder;
 ///
 /// impl BrowserConfigBuilder for MyBrowserConfigBuilder {
-///    fn build_browser_config(&self) -> crate::Result<BrowserConfig> {
-///       let mut browser_config = BrowserConfig::builder();
+///    fn build_browser_config(&self) -> pinterest_login::Result<BrowserConfig> {
+///       let browser_config = BrowserConfig::builder();
 ///
 ///      // Do whatever you want with the browser config
-///      browser_config.headless(true); // For example, set the browser to headless mode
+///      let browser_config = browser_config.with_head(); // For example, show the browser window
 ///
 ///     // Build the browser config and return it
 ///      browser_config.build().map_err(PinterestLoginError::BrowserConfigBuildError)
@@ -32,20 +34,36 @@ pub trait BrowserConfigBuilder {
 }
 
 /// The default browser config builder, that provides a method to build a chromiumoxide browser config
-/// This builder enables you to set the headless mode, the request timeout and the launch timeout
+/// This builder enables you to set the headless mode, the request timeout, the launch timeout, the proxy,
+/// the browser profile, executable, window size, locale and timezone, and extra chromium arguments
 ///
 /// # Example
-/// ```
+/// ```no_run
 /// # use pinterest_login::config_builder::{BrowserConfigBuilder, DefaultBrowserConfigBuilder};
 /// use std::time::Duration;
 ///
-/// let browser_config_builder = DefaultBrowserConfigBuilder::new(true, Duration::from_secs(3).into(), None);
+/// let browser_config_builder = DefaultBrowserConfigBuilder::new(true, Duration::from_secs(3).into(), None)
+///     // Reuse the same profile, so pinterest recognizes the device on the next logins
+///     .with_user_data_dir("profiles/my_account")
+///     .with_window_size(1366, 768)
+///     .with_locale("en-US")
+///     .with_timezone("America/New_York");
 /// let browser_config = browser_config_builder.build_browser_config()?;
+/// # Ok::<(), pinterest_login::PinterestLoginError>(())
 /// ```
+#[derive(Debug, Clone)]
 pub struct DefaultBrowserConfigBuilder {
     headless: bool,
     request_timeout: Option<std::time::Duration>,
-    launch_timeout: Option<std::time::Duration>
+    launch_timeout: Option<std::time::Duration>,
+    proxy: Option<String>,
+    user_data_dir: Option<PathBuf>,
+    executable: Option<PathBuf>,
+    window_size: Option<(u32, u32)>,
+    locale: Option<String>,
+    timezone: Option<String>,
+    args: Vec<String>,
+    sandbox: bool,
 }
 
 impl DefaultBrowserConfigBuilder {
@@ -59,9 +77,78 @@ impl DefaultBrowserConfigBuilder {
         Self {
             headless,
             request_timeout,
-            launch_timeout
+            launch_timeout,
+            proxy: None,
+            user_data_dir: None,
+            executable: None,
+            window_size: None,
+            locale: None,
+            timezone: None,
+            args: Vec::new(),
+            sandbox: true,
         }
     }
+
+    /// Sends the browser traffic through a proxy, like `http://host:port` or `socks5://host:port`
+    ///
+    /// Chromium doesn't accept credentials in the proxy url, use a proxy that authenticates by ip
+    pub fn with_proxy<S: Into<String>>(mut self, proxy: S) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Uses a persistent browser profile directory, created if it doesn't exist
+    ///
+    /// The repeated logins from the same profile reuse the device trust, and trigger far fewer challenges.
+    /// Without it, every login gets a new temporary profile that is removed afterwards
+    pub fn with_user_data_dir<P: Into<PathBuf>>(mut self, user_data_dir: P) -> Self {
+        self.user_data_dir = Some(user_data_dir.into());
+        self
+    }
+
+    /// Uses the given chromium executable, instead of detecting it
+    pub fn with_executable<P: Into<PathBuf>>(mut self, executable: P) -> Self {
+        self.executable = Some(executable.into());
+        self
+    }
+
+    /// Sets the window size and the viewport of the browser, in pixels
+    pub fn with_window_size(mut self, width: u32, height: u32) -> Self {
+        self.window_size = Some((width, height));
+        self
+    }
+
+    /// Sets the browser locale, like `en-US`, it's used for the `Accept-Language` header and `navigator.language`
+    pub fn with_locale<S: Into<String>>(mut self, locale: S) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Sets the browser timezone, like `Europe/Paris`
+    pub fn with_timezone<S: Into<String>>(mut self, timezone: S) -> Self {
+        self.timezone = Some(timezone.into());
+        self
+    }
+
+    /// Adds an extra chromium command line argument, like `--disable-gpu`
+    pub fn with_arg<S: Into<String>>(mut self, arg: S) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Adds extra chromium command line arguments
+    pub fn with_args<I: IntoIterator<Item = S>, S: Into<String>>(mut self, args: I) -> Self {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Enables or disables the chromium sandbox, enabled by default
+    ///
+    /// The sandbox has to be disabled to run as root, in most docker containers for example
+    pub fn with_sandbox(mut self, sandbox: bool) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
 }
 
 impl BrowserConfigBuilder for DefaultBrowserConfigBuilder {
@@ -93,6 +180,66 @@ impl BrowserConfigBuilder for DefaultBrowserConfigBuilder {
             browser_config_builder = browser_config_builder.launch_timeout(timeout);
         }
         
+        if let Some(proxy) = &self.proxy {
+            #[cfg(feature = "debug")] {
+                trace!("Setting the proxy server to {}", proxy);
+            }
+            browser_config_builder = browser_config_builder.arg(format!("--proxy-server={proxy}"));
+        }
+
+        if let Some(user_data_dir) = &self.user_data_dir {
+            #[cfg(feature = "debug")] {
+                trace!("Setting the user data dir to {:?}", user_data_dir);
+            }
+            browser_config_builder = browser_config_builder.user_data_dir(user_data_dir);
+        }
+
+        if let Some(executable) = &self.executable {
+            #[cfg(feature = "debug")] {
+                trace!("Setting the executable to {:?}", executable);
+            }
+            browser_config_builder = browser_config_builder.chrome_executable(executable);
+        }
+
+        if let Some((width, height)) = self.window_size {
+            #[cfg(feature = "debug")] {
+                trace!("Setting the window size to {}x{}", width, height);
+            }
+            browser_config_builder = browser_config_builder
+                .window_size(width, height)
+                .viewport(Viewport {
+                    width,
+                    height,
+                    ..Viewport::default()
+                });
+        }
+
+        if let Some(locale) = &self.locale {
+            #[cfg(feature = "debug")] {
+                trace!("Setting the locale to {}", locale);
+            }
+            // `--lang` is used on windows and macos, `LANGUAGE` on linux
+            browser_config_builder = browser_config_builder
+                .arg(format!("--lang={locale}"))
+                .env("LANGUAGE", locale.replace('-', "_"));
+        }
+
+        if let Some(timezone) = &self.timezone {
+            #[cfg(feature = "debug")] {
+                trace!("Setting the timezone to {}", timezone);
+            }
+            browser_config_builder = browser_config_builder.env("TZ", timezone);
+        }
+
+        if !self.sandbox {
+            #[cfg(feature = "debug")] {
+                trace!("Disabling the sandbox");
+            }
+            browser_config_builder = browser_config_builder.no_sandbox();
+        }
+
+        browser_config_builder = browser_config_builder.args(&self.args);
+
         #[cfg(feature = "debug")] {
             info!("Built browser config");
             trace!("Browser config: {:?}", browser_config_builder);
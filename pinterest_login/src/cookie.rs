@@ -0,0 +1,146 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use chromiumoxide::cdp::browser_protocol::network::{Cookie, CookieSameSite};
+use serde::{Deserialize, Serialize};
+
+/// The `SameSite` attribute of a cookie
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// A browser cookie with all its attributes, as returned by the login
+///
+/// # Example
+/// ```
+/// use pinterest_login::cookie::SessionCookie;
+///
+/// let cookie = SessionCookie::new("csrftoken", "1234");
+/// assert_eq!(cookie.domain, ".pinterest.com");
+/// assert!(!cookie.is_expired());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionCookie {
+    /// The cookie name
+    pub name: String,
+    /// The cookie value
+    pub value: String,
+    /// The cookie domain, a leading dot means the subdomains are included
+    pub domain: String,
+    /// The cookie path
+    pub path: String,
+    /// The expiry time as seconds since the unix epoch, `None` for session cookies
+    #[serde(default)]
+    pub expires: Option<f64>,
+    /// Whether the cookie is only sent over https
+    #[serde(default)]
+    pub secure: bool,
+    /// Whether the cookie is hidden from javascript
+    #[serde(default)]
+    pub http_only: bool,
+    /// The `SameSite` attribute of the cookie, if any
+    #[serde(default)]
+    pub same_site: Option<SameSite>,
+}
+
+impl SessionCookie {
+    /// Creates a new session cookie for all the pinterest subdomains, with the path `/` and no expiry
+    pub fn new<N: Into<String>, V: Into<String>>(name: N, value: V) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            domain: ".pinterest.com".to_string(),
+            path: "/".to_string(),
+            expires: None,
+            secure: true,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// Returns the expiry time of the cookie, `None` for session cookies,
+    /// and for the expiries that the system time can't represent (like `i64::MAX` in a cookies.txt file)
+    pub fn expires_at(&self) -> Option<SystemTime> {
+        UNIX_EPOCH.checked_add(Duration::try_from_secs_f64(self.expires?).ok()?)
+    }
+
+    /// Returns true if the cookie is expired at the given time
+    pub fn is_expired_at(&self, now: SystemTime) -> bool {
+        self.expires_at().is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Returns true if the cookie is expired now
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.is_expired_at(SystemTime::now())
+    }
+
+    /// Returns the url the cookie belongs to, like `https://pinterest.com/`
+    pub fn url(&self) -> String {
+        let scheme = if self.secure { "https" } else { "http" };
+        format!("{scheme}://{}{}", self.domain.trim_start_matches('.'), self.path)
+    }
+
+    /// Returns the cookie as a `Set-Cookie` header value, the expiry is written as a `Max-Age` relative to `now`
+    pub fn to_set_cookie_string(&self, now: SystemTime) -> String {
+        let mut set_cookie = format!("{}={}; Path={}", self.name, self.value, self.path);
+
+        if self.domain.starts_with('.') {
+            set_cookie.push_str("; Domain=");
+            set_cookie.push_str(self.domain.trim_start_matches('.'));
+        }
+        if let Some(expires_at) = self.expires_at() {
+            let max_age = expires_at.duration_since(now).map(|d| d.as_secs()).unwrap_or(0);
+            set_cookie.push_str(&format!("; Max-Age={max_age}"));
+        }
+        if self.secure {
+            set_cookie.push_str("; Secure");
+        }
+        if self.http_only {
+            set_cookie.push_str("; HttpOnly");
+        }
+        if let Some(same_site) = self.same_site {
+            set_cookie.push_str(&format!("; SameSite={same_site:?}"));
+        }
+
+        set_cookie
+    }
+}
+
+impl From<Cookie> for SessionCookie {
+    fn from(cookie: Cookie) -> Self {
+        Self {
+            name: cookie.name,
+            value: cookie.value,
+            domain: cookie.domain,
+            path: cookie.path,
+            // Chromium uses -1 for the session cookies
+            expires: (!cookie.session && cookie.expires >= 0.0).then_some(cookie.expires),
+            secure: cookie.secure,
+            http_only: cookie.http_only,
+            same_site: cookie.same_site.map(|same_site| match same_site {
+                CookieSameSite::Strict => SameSite::Strict,
+                CookieSameSite::Lax => SameSite::Lax,
+                CookieSameSite::None => SameSite::None,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expires_at_handles_the_out_of_range_expiries() {
+        let cookie = |expires| SessionCookie { expires: Some(expires), ..SessionCookie::new("name", "value") };
+
+        assert_eq!(cookie(1_700_000_000.0).expires_at(), Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)));
+        for expires in [i64::MAX as f64, f64::MAX, f64::INFINITY, f64::NAN, -1.0] {
+            assert_eq!(cookie(expires).expires_at(), None, "{expires}");
+            assert!(!cookie(expires).is_expired());
+        }
+        assert!(!cookie(i64::MAX as f64).to_set_cookie_string(SystemTime::now()).contains("Max-Age"));
+    }
+}
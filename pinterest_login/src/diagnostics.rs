@@ -0,0 +1,84 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use chromiumoxide::page::ScreenshotParams;
+use chromiumoxide::Page;
+#[cfg(feature = "debug")]
+use log::{info, warn};
+
+use crate::cookie::SessionCookie;
+use crate::PinterestLoginError;
+
+/// The state of the page when the login failed, saved to the diagnostics directory
+///
+/// Every artifact is saved on a best effort basis, the missing ones couldn't be captured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginDiagnostics {
+    /// The directory of this failure, inside the diagnostics directory
+    pub dir: PathBuf,
+    /// The full page screenshot, as png
+    pub screenshot: Option<PathBuf>,
+    /// The page html
+    pub html: Option<PathBuf>,
+    /// The url of the page
+    pub url: Option<String>,
+    /// The page cookies, as a json list of [`SessionCookie`]
+    pub cookies: Option<PathBuf>,
+}
+
+/// Saves the screenshot, the html, the url and the cookies of the page to a new directory inside `diagnostics_dir`,
+/// and attaches their paths to the error
+///
+/// The error is returned as is if the directory can't be created
+pub(crate) async fn capture(page: &Page, diagnostics_dir: &Path, error: PinterestLoginError) -> PinterestLoginError {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or_default();
+    let dir = diagnostics_dir.join(format!("login-failure-{millis}"));
+
+    #[cfg(feature = "debug")]
+    info!("Saving the login diagnostics to: {:?}", dir);
+    if let Err(_e) = std::fs::create_dir_all(&dir) {
+        #[cfg(feature = "debug")]
+        warn!("Couldn't create the diagnostics directory: {}", _e);
+        return error;
+    }
+
+    let screenshot = match page.screenshot(ScreenshotParams::builder().full_page(true).build()).await {
+        Ok(png) => save(dir.join("screenshot.png"), png),
+        Err(_e) => {
+            #[cfg(feature = "debug")]
+            warn!("Couldn't take the screenshot: {}", _e);
+            None
+        }
+    };
+    let html = match page.content().await {
+        Ok(html) => save(dir.join("page.html"), html),
+        Err(_) => None,
+    };
+    let url = page.url().await.ok().flatten();
+    if let Some(url) = &url {
+        save(dir.join("url.txt"), url);
+    }
+    let cookies = match page.get_cookies().await {
+        Ok(cookies) => {
+            let cookies: Vec<SessionCookie> = cookies.into_iter().map(SessionCookie::from).collect();
+            serde_json::to_vec_pretty(&cookies).ok().and_then(|json| save(dir.join("cookies.json"), json))
+        }
+        Err(_) => None,
+    };
+
+    PinterestLoginError::WithDiagnostics {
+        source: Box::new(error),
+        diagnostics: Box::new(LoginDiagnostics { dir, screenshot, html, url, cookies }),
+    }
+}
+
+/// Writes an artifact, returns its path if it was written
+fn save<C: AsRef<[u8]>>(path: PathBuf, contents: C) -> Option<PathBuf> {
+    match std::fs::write(&path, contents) {
+        Ok(()) => Some(path),
+        Err(_e) => {
+            #[cfg(feature = "debug")]
+            warn!("Couldn't save {:?}: {}", path, _e);
+            None
+        }
+    }
+}
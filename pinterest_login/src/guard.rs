@@ -0,0 +1,174 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chromiumoxide::Browser;
+use futures::FutureExt;
+#[cfg(feature = "debug")]
+use log::{debug, info, warn};
+
+use crate::runtime;
+use crate::spawner::Spawner;
+
+/// How many times the removal of the temporary profile is tried,
+/// the helper processes of chromium may still hold files in it right after the browser exits
+const PROFILE_REMOVAL_ATTEMPTS: u32 = 10;
+/// The delay between two removals of the temporary profile
+const PROFILE_REMOVAL_DELAY: Duration = Duration::from_millis(100);
+
+/// Owns the browser for the duration of the login, and tears it down on every path
+///
+/// [`BrowserGuard::close`] closes the browser gracefully (and kills it if it doesn't exit in time),
+/// if the guard is dropped without being closed (on a panic, or when the login future is cancelled),
+/// the browser process is killed and waited for. The temporary profile directory is removed in both cases
+///
+/// A browser we connected to is left running, only the incognito context of the login is disposed,
+/// in the background with the spawner of the login when the guard is dropped
+pub(crate) struct BrowserGuard {
+    browser: Option<Browser>,
+    temp_profile: Option<PathBuf>,
+    /// The spawner of the login, set for a browser we connected to
+    spawner: Option<Arc<dyn Spawner>>,
+    close_timeout: Duration,
+}
+
+impl BrowserGuard {
+    /// Creates a new guard for a browser launched for the login
+    ///
+    /// # Arguments
+    /// * `browser` - The launched browser
+    /// * `temp_profile` - The temporary profile directory created for this login, removed on teardown
+    /// * `close_timeout` - How long to wait for the browser to exit
+    pub(crate) fn launched(browser: Browser, temp_profile: Option<PathBuf>, close_timeout: Duration) -> Self {
+        Self {
+            browser: Some(browser),
+            temp_profile,
+            spawner: None,
+            close_timeout,
+        }
+    }
+
+    /// Creates a new guard for a running browser we connected to, with the login in an incognito context
+    ///
+    /// # Arguments
+    /// * `browser` - The connected browser
+    /// * `spawner` - Spawns the disposal of the incognito context when the guard is dropped
+    /// * `close_timeout` - How long to wait for the incognito context to be disposed
+    pub(crate) fn connected(browser: Browser, spawner: Arc<dyn Spawner>, close_timeout: Duration) -> Self {
+        Self {
+            browser: Some(browser),
+            temp_profile: None,
+            spawner: Some(spawner),
+            close_timeout,
+        }
+    }
+
+    /// Returns the browser
+    pub(crate) fn browser(&self) -> &Browser {
+        self.browser.as_ref().expect("the browser is only taken on teardown")
+    }
+
+    /// Closes the browser, and kills it if it doesn't exit within the close timeout
+    pub(crate) async fn close(mut self) {
+        let Some(mut browser) = self.browser.take() else {
+            return;
+        };
+
+        if self.spawner.is_some() {
+            #[cfg(feature = "debug")]
+            info!("Disposing the incognito context of the login");
+            let _ = runtime::timeout(self.close_timeout, browser.quit_incognito_context()).await;
+            return;
+        }
+
+        #[cfg(feature = "debug")]
+        info!("Closing the browser");
+        let closed = runtime::timeout(self.close_timeout, async {
+            browser.close().await.is_ok() && browser.wait().await.is_ok()
+        }).await;
+
+        if closed != Some(true) {
+            #[cfg(feature = "debug")]
+            warn!("The browser didn't close within {:?}, killing it", self.close_timeout);
+            if let Some(Err(_e)) = browser.kill().await {
+                #[cfg(feature = "debug")]
+                warn!("Couldn't kill the browser: {}", _e);
+            }
+        }
+        drop(browser);
+
+        if let Some(dir) = self.temp_profile.take() {
+            for attempt in 1..=PROFILE_REMOVAL_ATTEMPTS {
+                if remove_temp_profile(&dir, attempt) {
+                    break;
+                }
+                runtime::sleep(PROFILE_REMOVAL_DELAY).await;
+            }
+        }
+    }
+}
+
+/// Removes the temporary profile, returns false if it should be tried again
+fn remove_temp_profile(dir: &Path, attempt: u32) -> bool {
+    #[cfg(feature = "debug")]
+    debug!("Removing the temporary profile: {:?}", dir);
+    match std::fs::remove_dir_all(dir) {
+        Ok(()) => true,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => true,
+        Err(_e) if attempt < PROFILE_REMOVAL_ATTEMPTS => false,
+        Err(_e) => {
+            #[cfg(feature = "debug")]
+            warn!("Couldn't remove the temporary profile {:?}: {}", dir, _e);
+            true
+        }
+    }
+}
+
+/// Kills the browser process and waits for it to exit, up to the timeout
+fn kill_and_wait(browser: &mut Browser, timeout: Duration) {
+    let Some(child) = browser.get_mut_child().map(|child| child.as_mut_inner()) else {
+        return;
+    };
+    if let Err(_e) = child.kill() {
+        #[cfg(feature = "debug")]
+        warn!("Couldn't kill the browser: {}", _e);
+        return;
+    }
+
+    let deadline = Instant::now() + timeout;
+    while matches!(child.try_status(), Ok(None)) && Instant::now() < deadline {
+        std::thread::sleep(PROFILE_REMOVAL_DELAY);
+    }
+}
+
+impl Drop for BrowserGuard {
+    fn drop(&mut self) {
+        let Some(mut browser) = self.browser.take() else {
+            return;
+        };
+
+        if let Some(spawner) = self.spawner.take() {
+            #[cfg(feature = "debug")]
+            warn!("The login was interrupted, disposing the incognito context in the background");
+            let close_timeout = self.close_timeout;
+            // The handler task is still running, it's only cancelled once the login is done
+            spawner.spawn(async move {
+                let _ = runtime::timeout(close_timeout, browser.quit_incognito_context()).await;
+            }.boxed());
+            return;
+        }
+
+        #[cfg(feature = "debug")]
+        warn!("The browser was not closed, killing it");
+        kill_and_wait(&mut browser, self.close_timeout);
+        drop(browser);
+
+        if let Some(dir) = self.temp_profile.take() {
+            for attempt in 1..=PROFILE_REMOVAL_ATTEMPTS {
+                if remove_temp_profile(&dir, attempt) {
+                    break;
+                }
+                std::thread::sleep(PROFILE_REMOVAL_DELAY);
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::Duration;
+use chromiumoxide::layout::Point;
+use chromiumoxide::{Element, Page};
+#[cfg(feature = "debug")]
+use log::trace;
+
+use crate::runtime;
+
+/// The options of the human-like interaction of the default login bot, see [`crate::login_bot::DefaultBrowserLoginBot::with_interaction`]
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use pinterest_login::human::InteractionOptions;
+/// use pinterest_login::login_bot::DefaultBrowserLoginBot;
+///
+/// let bot = DefaultBrowserLoginBot::new("email", "password")
+///     .with_interaction(InteractionOptions::default()
+///         .with_keystroke_delay(Duration::from_millis(120), Duration::from_millis(60)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InteractionOptions {
+    keystroke_delay: Duration,
+    keystroke_jitter: Duration,
+    field_pause: (Duration, Duration),
+    mouse_movement: bool,
+    mouse_steps: u32,
+}
+
+impl Default for InteractionOptions {
+    /// Creates the default human-like options: 90ms ± 50ms per keystroke, a 400 to 1200ms pause between the fields,
+    /// and the mouse moved toward the elements in 12 steps before clicking them
+    fn default() -> Self {
+        Self {
+            keystroke_delay: Duration::from_millis(90),
+            keystroke_jitter: Duration::from_millis(50),
+            field_pause: (Duration::from_millis(400), Duration::from_millis(1200)),
+            mouse_movement: true,
+            mouse_steps: 12,
+        }
+    }
+}
+
+impl InteractionOptions {
+    /// Sets the delay after every keystroke, and the random jitter added to or removed from it
+    pub fn with_keystroke_delay(mut self, delay: Duration, jitter: Duration) -> Self {
+        self.keystroke_delay = delay;
+        self.keystroke_jitter = jitter;
+        self
+    }
+
+    /// Sets the range of the random pause between the fields, and before submitting the form
+    pub fn with_field_pause(mut self, min: Duration, max: Duration) -> Self {
+        self.field_pause = (min, max.max(min));
+        self
+    }
+
+    /// Enables or disables moving the mouse toward the elements before clicking them, and sets the number of steps
+    pub fn with_mouse_movement(mut self, enabled: bool, steps: u32) -> Self {
+        self.mouse_movement = enabled;
+        self.mouse_steps = steps.max(1);
+        self
+    }
+
+    /// Types the text one character at a time
+    pub(crate) async fn type_text(&self, element: &Element, text: &str) -> crate::Result<()> {
+        let mut buffer = [0u8; 4];
+        for c in text.chars() {
+            element.type_str(c.encode_utf8(&mut buffer)).await?;
+            // A random delay between `delay - jitter` and `delay + jitter`
+            let delay = (self.keystroke_delay + self.keystroke_jitter.mul_f64(2.0 * random_unit()))
+                .saturating_sub(self.keystroke_jitter);
+            runtime::sleep(delay).await;
+        }
+        Ok(())
+    }
+
+    /// Waits for a random pause, like a user moving to the next field
+    pub(crate) async fn pause(&self) {
+        let (min, max) = self.field_pause;
+        runtime::sleep(min + (max - min).mul_f64(random_unit())).await;
+    }
+
+    /// Moves the mouse toward the element along a slightly curved path, and clicks it
+    pub(crate) async fn click(&self, page: &Page, element: &Element, mouse: &Mutex<Point>) -> crate::Result<()> {
+        if !self.mouse_movement {
+            element.click().await?;
+            return Ok(());
+        }
+
+        let target = element.scroll_into_view().await?.clickable_point().await?;
+        let start = *mouse.lock().unwrap_or_else(|e| e.into_inner());
+        // The control point of the curve is off the straight line, the way a hand moves
+        let control = Point::new((start.x + target.x) / 2.0 + (random_unit() - 0.5) * 120.0,
+                                 (start.y + target.y) / 2.0 + (random_unit() - 0.5) * 120.0);
+
+        #[cfg(feature = "debug")]
+        trace!("Moving the mouse from {:?} to {:?}", start, target);
+        for step in 1..=self.mouse_steps {
+            let t = step as f64 / self.mouse_steps as f64;
+            let point = Point::new(
+                (1.0 - t).powi(2) * start.x + 2.0 * (1.0 - t) * t * control.x + t.powi(2) * target.x,
+                (1.0 - t).powi(2) * start.y + 2.0 * (1.0 - t) * t * control.y + t.powi(2) * target.y,
+            );
+            page.move_mouse(point).await?;
+            runtime::sleep(Duration::from_millis(8 + (random_unit() * 16.0) as u64)).await;
+        }
+
+        page.click(target).await?;
+        *mouse.lock().unwrap_or_else(|e| e.into_inner()) = target;
+        Ok(())
+    }
+}
+
+/// Returns a random number between 0 and 1, the random state is seeded differently on every call
+fn random_unit() -> f64 {
+    (RandomState::new().build_hasher().finish() >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -62,7 +62,7 @@
 //! ```
 //!
 //! ## With `tokio` runtime
-//! ```no_run
+//! ```ignore
 //! use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
 //! use pinterest_login::login;
 //! use pinterest_login::login_bot::DefaultBrowserLoginBot;
@@ -91,21 +91,49 @@
 //! ```
 //!
 //! # Features
-//! * `async-std-runtime`: Use the async-std runtime instead of tokio (enabled by default)
-//! * `tokio-runtime`: Use the tokio runtime instead of async-std
+//! * `async-std-runtime`: Spawn the browser task on async-std (enabled by default)
+//! * `tokio-runtime`: Spawn the browser task on tokio, when the login runs inside a tokio runtime
 //! * `debug`: Enable debug logging
+//! * `http-login`: Enable the login over plain http, without a browser, see the `http_login` module
+//!
+//! Both runtime features can be enabled together. Without any of them the browser task runs on its own thread,
+//! or on the executor set with `LoginOptions::with_spawner`, like smol or glommio
+//!
+//! Whatever the runtime features, chromiumoxide always uses its async-std backend, which runs the browser
+//! connection on its own reactor so it works under any executor. This means async-std is built
+//! with the tokio-only builds too, the runtime features only choose where the browser task is spawned
 //!
 //!
 //! > This project is part of the [pinterest_rs](https://github.com/anas-elgarhy/pinterest-rs) project
+
 pub mod config_builder;
+pub mod cookie;
+pub mod diagnostics;
+pub mod human;
+#[cfg(feature = "http-login")]
+pub mod http_login;
+mod guard;
 pub mod login_bot;
+pub mod oauth;
+pub mod selectors;
+pub mod stealth;
+pub mod two_factor;
+mod runtime;
+pub mod spawner;
 
-use std::collections::HashMap;
 // #[cfg(feature = "async-std-runtime")]
 // use async_std::prelude::StreamExt;
-use futures::StreamExt;
-use chromiumoxide::Browser;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use chromiumoxide::{Browser, Page};
 use crate::config_builder::BrowserConfigBuilder;
+use crate::cookie::SessionCookie;
+use crate::diagnostics::LoginDiagnostics;
+use crate::guard::BrowserGuard;
+use crate::runtime::HandlerTask;
+use crate::spawner::{default_spawner, Spawner};
+use crate::stealth::StealthOptions;
 use crate::login_bot::BrowserLoginBot;
 #[cfg(feature = "debug")]
 use log::{info, trace, debug};
@@ -119,19 +147,156 @@ pub enum PinterestLoginError {
     /// Chromiumoxide error, returned when chromiumoxide fails to connect to the browser or when the browser fails to load the login page or when the timeout is reached
     /// See [chromiumoxide::error::CdpError](https://docs.rs/chromiumoxide/latest/chromiumoxide/error/enum.CdpError.htm) for more details
     #[error("{0}")]
-    CdpError(#[from] chromiumoxide::error::CdpError),
+    CdpError(Box<chromiumoxide::error::CdpError>),
     /// The browser config builder failed to build the browser config
     #[error("{0}")]
     BrowserConfigBuildError(String),
-    /// The login bot failed to fill or submit the login form, or the authentication is incorrect
+    /// The email or password is incorrect
     #[error("Authentication error: The email or password you entered is incorrect.")]
     AuthenticationError,
+    /// The login landed on a page that is neither the success, a known challenge, nor the login form
+    #[error("The login landed on an unexpected page: {0}")]
+    UnexpectedPage(String),
+    /// The login was stopped by a captcha or a bot challenge, retry later or from a different ip or browser profile
+    #[error("The login was stopped by a captcha or a bot challenge")]
+    CaptchaChallenge,
+    /// The account is locked or suspended, it has to be recovered from the browser
+    #[error("The account is locked or suspended")]
+    AccountLocked,
+    /// Pinterest asks to confirm the login from the link sent to the account email
+    #[error("The login has to be confirmed from the account email")]
+    EmailVerificationRequired,
+    /// An element of the login flow was not found, the page layout probably changed
+    #[error("The element `{0}` was not found, the login page layout may have changed")]
+    SelectorNotFound(String),
+    /// The account has two-factor authentication enabled, and the login bot has no code provider
+    #[error("The account requires a two-factor verification code")]
+    TwoFactorRequired,
+    /// The two-factor verification code was rejected
+    #[error("The two-factor verification code was rejected")]
+    TwoFactorCodeRejected,
+    /// The code provider failed to provide the two-factor verification code
+    #[error("Couldn't get the two-factor verification code: {0}")]
+    TwoFactorCodeUnavailable(String),
+    /// The OAuth provider button didn't open the provider login, in a popup or in the same window
+    #[error("The {0} login window didn't open")]
+    OAuthWindowNotFound(String),
+    /// A request of the http login failed
+    #[cfg(feature = "http-login")]
+    #[error("The http login request failed: {0}")]
+    HttpError(String),
+    /// The TOTP secret is not valid base32
+    #[error("The TOTP secret is not valid base32")]
+    InvalidTotpSecret,
+    /// The login failed, and the state of the page was saved to the diagnostics directory
+    #[error("{source} (the diagnostics were saved to {})", diagnostics.dir.display())]
+    WithDiagnostics {
+        /// The error that stopped the login
+        source: Box<PinterestLoginError>,
+        /// The paths of the saved diagnostics
+        diagnostics: Box<LoginDiagnostics>,
+    },
+}
+
+impl From<chromiumoxide::error::CdpError> for PinterestLoginError {
+    fn from(error: chromiumoxide::error::CdpError) -> Self {
+        Self::CdpError(Box::new(error))
+    }
+}
+
+#[cfg(feature = "http-login")]
+impl From<reqwest::Error> for PinterestLoginError {
+    fn from(error: reqwest::Error) -> Self {
+        Self::HttpError(error.to_string())
+    }
+}
+
+impl PinterestLoginError {
+    /// Returns the error that stopped the login, without the diagnostics
+    pub fn root_cause(&self) -> &PinterestLoginError {
+        match self {
+            Self::WithDiagnostics { source, .. } => source.root_cause(),
+            error => error,
+        }
+    }
+
+    /// Returns the diagnostics saved when the login failed, if any
+    pub fn diagnostics(&self) -> Option<&LoginDiagnostics> {
+        match self {
+            Self::WithDiagnostics { diagnostics, .. } => Some(diagnostics),
+            _ => None,
+        }
+    }
 }
 
 /// A type alias for `Result<T, PinterestLoginError>`
 pub type Result<T> = std::result::Result<T, PinterestLoginError>;
 
-/// Logs into Pinterest and returns the cookies as a HashMap
+/// The options of the login flow, see [`login_with_options`]
+#[derive(Debug, Clone)]
+pub struct LoginOptions {
+    diagnostics_dir: Option<PathBuf>,
+    close_timeout: Duration,
+    stealth: Option<StealthOptions>,
+    spawner: Option<Arc<dyn Spawner>>,
+}
+
+impl Default for LoginOptions {
+    /// Creates the default login options, with no diagnostics directory and a 5 seconds close timeout
+    fn default() -> Self {
+        Self {
+            diagnostics_dir: None,
+            close_timeout: Duration::from_secs(5),
+            stealth: None,
+            spawner: None,
+        }
+    }
+}
+
+impl LoginOptions {
+    /// Creates the default login options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Saves a full page screenshot, the page html, the url and the cookies to a new directory inside `dir`
+    /// when the login fails, the error is wrapped in `WithDiagnostics` with their paths
+    pub fn with_diagnostics_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.diagnostics_dir = Some(dir.into());
+        self
+    }
+
+    /// Enables the stealth mode, that hides the signs of an automated headless browser, see [`StealthOptions`]
+    pub fn with_stealth(mut self, stealth: StealthOptions) -> Self {
+        self.stealth = Some(stealth);
+        self
+    }
+
+    /// Returns the stealth options, `None` if the stealth mode is disabled
+    pub fn stealth(&self) -> Option<&StealthOptions> {
+        self.stealth.as_ref()
+    }
+
+    /// Sets the spawner of the task that drives the browser connection, see [`Spawner`],
+    /// the runtime is picked by [`default_spawner`] when it's not set
+    pub fn with_spawner<S: Spawner + 'static>(mut self, spawner: S) -> Self {
+        self.spawner = Some(Arc::new(spawner));
+        self
+    }
+
+    /// Returns the spawner of the task that drives the browser connection
+    fn spawner(&self) -> Arc<dyn Spawner> {
+        self.spawner.clone().unwrap_or_else(default_spawner)
+    }
+
+    /// Sets how long to wait for the browser to close after the login, before killing it
+    pub fn with_close_timeout(mut self, close_timeout: Duration) -> Self {
+        self.close_timeout = close_timeout;
+        self
+    }
+}
+
+/// Logs into Pinterest and returns the cookies, with all their attributes
 ///
 /// # Arguments
 /// * `login_bot` - The login bot to use to fill and submit the login form
@@ -139,12 +304,12 @@ pub type Result<T> = std::result::Result<T, PinterestLoginError>;
 ///
 /// # Example
 /// ```no_run
-/// # use std::collections::HashMap;
 /// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::cookie::SessionCookie;
 /// # use pinterest_login::login;
 /// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
 ///
-/// async fn login_to_pinterest(email: &str, password: &str) -> pinterest_login::Result<HashMap<String, String>> {
+/// async fn login_to_pinterest(email: &str, password: &str) -> pinterest_login::Result<Vec<SessionCookie>> {
 ///     let browser_config_builder = DefaultBrowserConfigBuilder::default();
 ///     let bot = DefaultBrowserLoginBot::new(email, password);
 ///
@@ -157,60 +322,183 @@ pub type Result<T> = std::result::Result<T, PinterestLoginError>;
 /// * `CdpError` - If there is an error with chromiumoxide (like launching timeout, or request timeout, network error, etc.)  see [chromiumoxide::error::CdpError](https://docs.rs/chromiumoxide/latest/chromiumoxide/error/enum.CdpError.html) to see all the errors
 /// * `BrowserConfigBuildError` - If there is an error building the browser config
 /// * `AuthenticationError` - If the email or password is incorrect
+/// * `CaptchaChallenge` - If the login is stopped by a captcha or a bot challenge
+/// * `AccountLocked` - If the account is locked or suspended
+/// * `EmailVerificationRequired` - If the login has to be confirmed from the account email
+/// * `SelectorNotFound` - If an element of the login form was not found
+/// * `TwoFactorRequired` - If the account asks for a two-factor code, and the bot has no code provider
+/// * `TwoFactorCodeRejected` - If the two-factor code was rejected
+/// * `UnexpectedPage` - If the login landed on a page that is neither the success nor a known challenge
 ///
 #[inline]
 pub async fn login(login_bot: &dyn BrowserLoginBot, config_builder: &dyn BrowserConfigBuilder)
-                   -> Result<HashMap<String, String>> {
-    #[cfg(feature = "debug")]
-    info!("Launching the browser");
+                   -> Result<Vec<SessionCookie>> {
+    login_with_options(login_bot, config_builder, &LoginOptions::default()).await
+}
 
-    let (browser, mut handler) = Browser::launch(config_builder.build_browser_config()?).await?;
+/// Logs into Pinterest with the given options and returns the cookies, with all their attributes
+///
+/// # Example
+/// ```no_run
+/// # use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// use pinterest_login::{login_with_options, LoginOptions};
+///
+/// # async fn run() {
+/// let bot = DefaultBrowserLoginBot::new("email", "password");
+/// let options = LoginOptions::new().with_diagnostics_dir("login-diagnostics");
+///
+/// if let Err(e) = login_with_options(&bot, &DefaultBrowserConfigBuilder::default(), &options).await {
+///     if let Some(diagnostics) = e.diagnostics() {
+///         eprintln!("See the screenshot at {:?}", diagnostics.screenshot);
+///     }
+/// }
+/// # }
+/// ```
+///
+/// # Errors
+/// See [`login`], the errors are wrapped in `WithDiagnostics` when a diagnostics directory is set
+pub async fn login_with_options(login_bot: &dyn BrowserLoginBot, config_builder: &dyn BrowserConfigBuilder,
+                                options: &LoginOptions) -> Result<Vec<SessionCookie>> {
+    let mut browser_config = config_builder.build_browser_config()?;
+    // Chromium uses a shared profile directory by default, give every login its own one, and remove it afterwards
+    let temp_profile = match browser_config.user_data_dir {
+        Some(_) => None,
+        None => {
+            let dir = temp_profile_dir();
+            browser_config.user_data_dir = Some(dir.clone());
+            Some(dir)
+        }
+    };
 
     #[cfg(feature = "debug")]
-    info!("The browser has been launched\nBrowser version: {:?}", browser.version().await?);
+    info!("Launching the browser");
 
-    #[cfg(feature = "async-std-runtime")]
-    let handle = async_std::task::spawn(async move {
-        loop {
-            let _event = handler.next().await;
+    let (browser, handler) = match Browser::launch(browser_config).await {
+        Ok(launched) => launched,
+        Err(e) => {
+            if let Some(dir) = temp_profile {
+                let _ = std::fs::remove_dir_all(dir);
+            }
+            return Err(e.into());
         }
-    });
+    };
+    // Closes the browser and removes the temporary profile even if the login fails or panics
+    let guard = BrowserGuard::launched(browser, temp_profile, options.close_timeout);
+    let handler_task = runtime::spawn_handler(handler, options.spawner().as_ref());
 
-    #[cfg(all(feature = "tokio-runtime", not(feature = "async-std-runtime")))]
-    let handle = tokio::spawn(async move {
-        loop {
-            let _event = handler.next().await;
-        }
-    });
+    login_in_browser(guard, handler_task, login_bot, options).await
+}
 
+/// Logs into Pinterest with an already running browser, and returns the cookies, with all their attributes
+///
+/// The login runs in a new incognito context, so it doesn't share the cookies with the other users of the browser,
+/// and the context is disposed afterwards, the browser itself is left running
+///
+/// # Arguments
+/// * `debug_ws_url` - The DevTools websocket url of the browser, like `ws://127.0.0.1:9222/devtools/browser/<id>`,
+///   it's the `webSocketDebuggerUrl` returned by `http://127.0.0.1:9222/json/version`
+/// * `login_bot` - The login bot to use to fill and submit the login form
+/// * `options` - The login options
+///
+/// # Example
+/// ```no_run
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// use pinterest_login::{connect_and_login, LoginOptions};
+///
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let bot = DefaultBrowserLoginBot::new("email", "password");
+/// let cookies = connect_and_login("ws://browser:9222/devtools/browser/1234", &bot, &LoginOptions::default()).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// See [`login`], `CdpError` is returned if the browser can't be reached
+pub async fn connect_and_login(debug_ws_url: &str, login_bot: &dyn BrowserLoginBot, options: &LoginOptions)
+                               -> Result<Vec<SessionCookie>> {
     #[cfg(feature = "debug")]
-    info!("Navigating to the login page: {}", PINTEREST_LOGIN_URL);
+    info!("Connecting to the browser: {}", debug_ws_url);
 
-    let page = browser.new_page(PINTEREST_LOGIN_URL).await?;
-    page.wait_for_navigation().await?;
+    let (mut browser, handler) = Browser::connect(debug_ws_url).await?;
+    let spawner = options.spawner();
+    let handler_task = runtime::spawn_handler(handler, spawner.as_ref());
 
-    #[cfg(feature = "debug")] {
-        info!("The login page has been loaded");
-        trace!("The login page content: {}", page.content().await?);
-        debug!("The login page cookies: {:?}", page.get_cookies().await?);
-        info!("Filling the login form");
+    #[cfg(feature = "debug")]
+    debug!("Creating an incognito context for the login");
+    if let Err(e) = browser.start_incognito_context().await {
+        drop(browser);
+        handler_task.cancel();
+        return Err(e.into());
     }
-    // Fill the login form
-    login_bot.fill_login_form(&page).await?;
+    let guard = BrowserGuard::connected(browser, spawner, options.close_timeout);
+
+    login_in_browser(guard, handler_task, login_bot, options).await
+}
+
+/// Runs the login in the browser, and tears it down afterwards
+async fn login_in_browser(guard: BrowserGuard, handler_task: HandlerTask, login_bot: &dyn BrowserLoginBot,
+                          options: &LoginOptions) -> Result<Vec<SessionCookie>> {
+    let cookies = login_with_browser(guard.browser(), login_bot, options).await;
+
+    // Close the browser
+    guard.close().await;
+
+    #[cfg(feature = "debug")]
+    info!("Canceling the event handler");
+    handler_task.cancel();
+
     #[cfg(feature = "debug")]
-    info!("Submitting the login form");
-    // Click the login button
-    login_bot.submit_login_form(&page).await?;
+    trace!("The cookies: {cookies:?}");
+
+    cookies
+}
 
+/// Opens the login page, and runs the login bot on it
+async fn login_with_browser(browser: &Browser, login_bot: &dyn BrowserLoginBot, options: &LoginOptions)
+                            -> Result<Vec<SessionCookie>> {
     #[cfg(feature = "debug")] {
-        info!("The login form has been submitted");
-        info!("Waiting for the login to complete, and checking if the login was successful");
+        info!("Browser version: {:?}", browser.version().await?);
+        info!("Navigating to the login page: {}", PINTEREST_LOGIN_URL);
+    }
+
+    let page = match &options.stealth {
+        Some(stealth) => {
+            // The evasion scripts have to be in place before the login page scripts run
+            let page = browser.new_page("about:blank").await?;
+            stealth.apply(&page).await?;
+            page.goto(PINTEREST_LOGIN_URL).await?;
+            page
+        }
+        None => browser.new_page(PINTEREST_LOGIN_URL).await?,
+    };
+    match (login_on_page(browser, login_bot, &page).await, &options.diagnostics_dir) {
+        (Err(error), Some(dir)) => {
+            #[cfg(feature = "debug")]
+            info!("The login failed, capturing the diagnostics: {}", error);
+            Err(diagnostics::capture(&page, dir, error).await)
+        }
+        (result, _) => result,
     }
-    // Check if the login was successful
-    login_bot.check_login(&page).await?;
+}
 
+/// Returns a new unique temporary profile directory
+fn temp_profile_dir() -> PathBuf {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+    std::env::temp_dir().join(format!("pinterest-login-{}-{nanos}", std::process::id()))
+}
 
-    let mut cookies = HashMap::with_capacity(5);
+/// Runs the login bot on the login page, and gets the cookies
+async fn login_on_page(browser: &Browser, login_bot: &dyn BrowserLoginBot, page: &Page) -> Result<Vec<SessionCookie>> {
+    page.wait_for_navigation().await?;
+
+    #[cfg(feature = "debug")] {
+        info!("The login page has been loaded");
+        trace!("The login page content: {}", page.content().await?);
+        debug!("The login page cookies: {:?}", page.get_cookies().await?);
+    }
+    // Fill and submit the login form, and check if the login was successful
+    login_bot.login(browser, page).await?;
 
     #[cfg(feature = "debug")]
     info!("The login was successful, getting the cookies");
@@ -224,30 +512,6 @@ pub async fn login(login_bot: &dyn BrowserLoginBot, config_builder: &dyn Browser
     }
 
     #[cfg(feature = "debug")]
-    info!("Collecting the cookies values and names into a HashMap");
-    for cookie in c {
-        #[cfg(feature = "debug")]
-        trace!("Inserting the cookie: {} : {}", cookie.name, cookie.value);
-
-        cookies.insert(cookie.name, cookie.value);
-    }
-
-    #[cfg(feature = "debug")]
-    info!("Canceling the event handler");
-    #[cfg(feature = "async-std-runtime")]
-    // Cancel the event handler
-    handle.cancel().await;
-    #[cfg(all(feature = "tokio-runtime", not(feature = "async-std-runtime")))]
-    // Cancel the event handler
-    handle.abort();
-
-    // #[cfg(feature = "debug")]
-    // info!("Closing the browser");
-    // Close the browser
-    // browser.close().await?;
-
-    #[cfg(feature = "debug")]
-    trace!("The cookies: {cookies:?}");
-
-    Ok(cookies)
+    info!("Converting the cookies into session cookies");
+    Ok(c.into_iter().map(SessionCookie::from).collect())
 }
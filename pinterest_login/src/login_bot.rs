@@ -1,8 +1,14 @@
-use chromiumoxide::Page;
+use std::sync::Mutex;
+use std::time::Instant;
+use chromiumoxide::layout::Point;
+use chromiumoxide::{Browser, Element, Page};
 #[cfg(feature = "debug")]
 use log::{info, trace, debug};
 
-use crate::{PINTEREST_LOGIN_URL, PinterestLoginError};
+use crate::{runtime, PinterestLoginError};
+use crate::human::InteractionOptions;
+use crate::selectors::{find_any, wait_for_any, LoginSelectors, POLL_INTERVAL};
+use crate::two_factor::TwoFactorCodeProvider;
 
 /// Trait for login bots, which are used to fill and submit the login form in the browser
 ///
@@ -15,27 +21,52 @@ use crate::{PINTEREST_LOGIN_URL, PinterestLoginError};
 ///
 /// #[async_trait::async_trait]
 /// impl BrowserLoginBot for MyLoginBot {
-///    async fn fill_login_form(&self, page: &Page) -> crate::Result<()> {
+///    async fn fill_login_form(&self, page: &Page) -> pinterest_login::Result<()> {
 ///        // ...
+///        # Ok(())
 ///    }
 ///
-///    async fn submit_login_form(&self, page: &Page) -> crate::Result<()> {
+///    async fn submit_login_form(&self, page: &Page) -> pinterest_login::Result<()> {
 ///        // ...
+///        # Ok(())
 ///    }
 ///
-///    async fn check_login(&self, page: &Page) -> crate::Result<()> {
+///    async fn check_login(&self, page: &Page) -> pinterest_login::Result<()> {
 ///        // ...
+///        # Ok(())
 ///    }
 /// }
 /// ```
 #[async_trait::async_trait]
-pub trait BrowserLoginBot {
+pub trait BrowserLoginBot: Sync {
     /// Fills the login form fields with the required data
     async fn fill_login_form(&self, page: &Page) -> crate::Result<()>;
     /// Submits the login form
     async fn submit_login_form(&self, page: &Page) -> crate::Result<()>;
     /// Checks if the login was successful
     async fn check_login(&self, page: &Page) -> crate::Result<()>;
+
+    /// Runs the whole login on the login page, the browser is given to drive the windows opened by the page,
+    /// like the OAuth popups
+    ///
+    /// The default implementation fills the login form, submits it, and checks if the login was successful
+    async fn login(&self, _browser: &Browser, page: &Page) -> crate::Result<()> {
+        #[cfg(feature = "debug")]
+        info!("Filling the login form");
+        // Fill the login form
+        self.fill_login_form(page).await?;
+        #[cfg(feature = "debug")]
+        info!("Submitting the login form");
+        // Click the login button
+        self.submit_login_form(page).await?;
+
+        #[cfg(feature = "debug")] {
+            info!("The login form has been submitted");
+            info!("Waiting for the login to complete, and checking if the login was successful");
+        }
+        // Check if the login was successful
+        self.check_login(page).await
+    }
 }
 
 /// The default login bot, that provides methods to fill and submit the login form in the browser
@@ -52,9 +83,18 @@ pub trait BrowserLoginBot {
 ///
 /// U don't need to use the login bot directly, it is used by the login function,
 /// you just send it to the login function and it will use it to fill and submit the login form
+///
+/// If the account has two-factor authentication enabled, set a code provider with [`DefaultBrowserLoginBot::with_two_factor`].
+/// The form is filled instantly by default, use [`DefaultBrowserLoginBot::with_interaction`] to type and click like a human.
+/// The selectors of the login page can be changed with [`DefaultBrowserLoginBot::with_selectors`]
 pub struct DefaultBrowserLoginBot<'a> {
     email: &'a str,
     password: &'a str,
+    selectors: LoginSelectors,
+    two_factor: Option<Box<dyn TwoFactorCodeProvider + 'a>>,
+    interaction: Option<InteractionOptions>,
+    /// The last mouse position, where the next mouse movement starts from
+    mouse: Mutex<Point>,
 }
 
 impl<'a> DefaultBrowserLoginBot<'a> {
@@ -67,36 +107,173 @@ impl<'a> DefaultBrowserLoginBot<'a> {
         Self {
             email,
             password,
+            selectors: LoginSelectors::default(),
+            two_factor: None,
+            interaction: None,
+            mouse: Mutex::new(Point::new(0.0, 0.0)),
+        }
+    }
+
+    /// Sets the selectors of the login page, and the condition that tells the login was successful
+    pub fn with_selectors(mut self, selectors: LoginSelectors) -> Self {
+        self.selectors = selectors;
+        self
+    }
+
+    /// Types and clicks like a human: a delay with jitter after every keystroke, random pauses between the fields,
+    /// and mouse movements toward the elements before clicking them, see [`InteractionOptions`]
+    pub fn with_interaction(mut self, interaction: InteractionOptions) -> Self {
+        self.interaction = Some(interaction);
+        self
+    }
+
+    /// Clicks the element, after moving the mouse toward it in the human-like mode
+    async fn click(&self, page: &Page, element: &Element) -> crate::Result<()> {
+        match &self.interaction {
+            Some(interaction) => interaction.click(page, element, &self.mouse).await,
+            None => {
+                element.click().await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Types the text in the element, one character at a time in the human-like mode
+    async fn type_text(&self, element: &Element, text: &str) -> crate::Result<()> {
+        match &self.interaction {
+            Some(interaction) => interaction.type_text(element, text).await,
+            None => {
+                element.type_str(text).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Pauses between two fields in the human-like mode
+    async fn pause(&self) {
+        if let Some(interaction) = &self.interaction {
+            interaction.pause().await;
         }
     }
+
+    /// Sets the provider of the verification codes, used when the login asks for a two-factor code
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+    /// use pinterest_login::two_factor::TotpCodeProvider;
+    ///
+    /// let bot = DefaultBrowserLoginBot::new("email", "password")
+    ///     .with_two_factor(TotpCodeProvider::new("JBSWY3DPEHPK3PXP")?);
+    /// # Ok::<(), pinterest_login::PinterestLoginError>(())
+    /// ```
+    pub fn with_two_factor<P: TwoFactorCodeProvider + 'a>(mut self, provider: P) -> Self {
+        self.two_factor = Some(Box::new(provider));
+        self
+    }
+
+    /// Fills and submits the two-factor code, when the login landed on the verification form
+    async fn submit_two_factor_code(&self, page: &Page, code_input: Element) -> crate::Result<()> {
+        #[cfg(feature = "debug")]
+        info!("The login asks for a two-factor code");
+        let Some(provider) = &self.two_factor else {
+            #[cfg(feature = "debug")]
+            debug!("There is no two-factor code provider");
+            return Err(PinterestLoginError::TwoFactorRequired);
+        };
+
+        let code = provider.code().await?;
+        #[cfg(feature = "debug")]
+        trace!("Entering the two-factor code: {}", code);
+
+        self.click(page, &code_input).await?;
+        self.type_text(&code_input, code.trim()).await?;
+        self.pause().await;
+        self.click(page, &wait_for_any(page, self.selectors.submit(), self.selectors.wait_timeout()).await?).await
+    }
+}
+
+/// The url fragments and the selectors of the captcha and bot challenges
+const CAPTCHA_URL_FRAGMENTS: [&str; 2] = ["captcha", "challenge"];
+const CAPTCHA_SELECTORS: [&str; 3] = ["iframe[src*='recaptcha']", "iframe[src*='hcaptcha']", "iframe[src*='arkoselabs']"];
+/// The url fragments of the locked and suspended accounts pages
+const LOCKED_URL_FRAGMENTS: [&str; 3] = ["suspended", "locked", "deactivated"];
+/// The url fragments of the email verification pages, shown for the suspicious logins,
+/// a bare `verify` would also match the two-factor verification page
+const EMAIL_VERIFICATION_URL_FRAGMENTS: [&str; 3] = ["checkpoint", "email_verification", "verify_email"];
+
+/// Finds an element on the page, a missing element means the page layout changed
+///
+/// # Errors
+/// * `SelectorNotFound` - If there is no element matching the selector
+pub async fn find_element(page: &Page, selector: &str) -> crate::Result<Element> {
+    page.find_element(selector).await.map_err(|_e| {
+        #[cfg(feature = "debug")]
+        debug!("The element {} was not found: {}", selector, _e);
+        PinterestLoginError::SelectorNotFound(selector.to_string())
+    })
+}
+
+/// Checks if the page is one of the challenges that stop the login, useful for the custom login bots
+///
+/// # Errors
+/// * `CaptchaChallenge` - If the page shows a captcha or a bot challenge
+/// * `AccountLocked` - If the account is locked or suspended
+/// * `EmailVerificationRequired` - If pinterest asks to confirm the login from the account email
+/// * `CdpError` - If the page url can't be read
+pub async fn check_login_challenges(page: &Page) -> crate::Result<()> {
+    let url = page.url().await?.unwrap_or_default().to_lowercase();
+    #[cfg(feature = "debug")]
+    debug!("Checking the login challenges at: {}", url);
+
+    let path = url.split_once("://").map_or(url.as_str(), |(_, rest)| rest);
+    let path = path.find('/').map_or("", |start| &path[start..]);
+    let path_contains = |fragments: &[&str]| fragments.iter().any(|fragment| path.contains(fragment));
+
+    if path_contains(&CAPTCHA_URL_FRAGMENTS) {
+        return Err(PinterestLoginError::CaptchaChallenge);
+    }
+    for selector in CAPTCHA_SELECTORS {
+        if page.find_element(selector).await.is_ok() {
+            #[cfg(feature = "debug")]
+            debug!("Found the captcha element: {}", selector);
+            return Err(PinterestLoginError::CaptchaChallenge);
+        }
+    }
+    if path_contains(&LOCKED_URL_FRAGMENTS) {
+        return Err(PinterestLoginError::AccountLocked);
+    }
+    if path_contains(&EMAIL_VERIFICATION_URL_FRAGMENTS) {
+        return Err(PinterestLoginError::EmailVerificationRequired);
+    }
+
+    Ok(())
 }
 
 #[async_trait::async_trait]
 impl BrowserLoginBot for DefaultBrowserLoginBot<'_> {
     #[inline(always)]
     async fn fill_login_form(&self, page: &Page) -> crate::Result<()> {
-        const EMAIL_INPUT_SELECTOR: &str = "input#email";
-        const PASSWORD_INPUT_SELECTOR: &str = "input#password";
-
         #[cfg(feature = "debug")] {
             trace!("Filling the login form with the email: {} and password: {}", self.email, self.password);
             debug!("entering the email");
-            trace!("Finding the email input field with the selector: {}", EMAIL_INPUT_SELECTOR);
+            trace!("Finding the email input field with the selectors: {:?}", self.selectors.email());
         }
         // Wait for the page to load, and then find the email input field and fill it
-        page.find_element(EMAIL_INPUT_SELECTOR).await?
-            .click().await?
-            .type_str(self.email).await?;
+        let email_input = wait_for_any(page, self.selectors.email(), self.selectors.wait_timeout()).await?;
+        self.click(page, &email_input).await?;
+        self.type_text(&email_input, self.email).await?;
+        self.pause().await;
 
         #[cfg(feature = "debug")] {
             debug!("Email entered successfully, entering the password");
-            trace!("Finding the password input field with the selector: {}", PASSWORD_INPUT_SELECTOR);
+            trace!("Finding the password input field with the selectors: {:?}", self.selectors.password());
         }
 
         // Find the password input field and fill it
-        page.find_element(PASSWORD_INPUT_SELECTOR).await?
-            .click().await?
-            .type_str(self.password).await?;
+        let password_input = wait_for_any(page, self.selectors.password(), self.selectors.wait_timeout()).await?;
+        self.click(page, &password_input).await?;
+        self.type_text(&password_input, self.password).await?;
 
         #[cfg(feature = "debug")]
         debug!("Password entered successfully");
@@ -106,16 +283,14 @@ impl BrowserLoginBot for DefaultBrowserLoginBot<'_> {
 
     #[inline(always)]
     async fn submit_login_form(&self, page: &Page) -> crate::Result<()> {
-        const LOGIN_BUTTON_SELECTOR: &str = "button[type='submit']";
-
         #[cfg(feature = "debug")] {
             debug!("Submitting the login form");
             info!("Finding the submit button and clicking it");
-            trace!("Finding the submit button with the selector: {}", LOGIN_BUTTON_SELECTOR);
+            trace!("Finding the submit button with the selectors: {:?}", self.selectors.submit());
         }
         // Find the submit button and click it
-        page.find_element(LOGIN_BUTTON_SELECTOR).await?
-            .click().await?;
+        self.pause().await;
+        self.click(page, &wait_for_any(page, self.selectors.submit(), self.selectors.wait_timeout()).await?).await?;
 
         #[cfg(feature = "debug")]
         debug!("Login form submitted successfully");
@@ -127,30 +302,56 @@ impl BrowserLoginBot for DefaultBrowserLoginBot<'_> {
     async fn check_login(&self, page: &Page) -> crate::Result<()> {
         #[cfg(feature = "debug")]
         debug!("Checking if the login was successful");
-        // Wait for the page to load, and then check if the login was successful
-        match page.wait_for_navigation().await?.url().await? {
-            None => {
+        // Poll the page until the login is successful, a challenge shows up, or the timeout elapses,
+        // a single navigation is not enough since the login can redirect several times
+        let mut deadline = Instant::now() + self.selectors.wait_timeout();
+        let mut code_submitted = false;
+        loop {
+            if self.selectors.success().is_met(page).await? {
                 #[cfg(feature = "debug")]
-                debug!("Couldn't get the url, the login was unsuccessful");
-                // If we can't get the url, then the login was unsuccessful
-                Err(PinterestLoginError::AuthenticationError)
+                info!("The success condition is met, the login was successful");
+                return Ok(());
             }
-            Some(url) => {
-                #[cfg(feature = "debug")] {
-                    debug!("Got the url: {}", url);
-                    info!("Checking if the url is the same as the login url");
+
+            // The accounts with two-factor authentication land on the verification form first
+            if !code_submitted {
+                if let Some(code_input) = find_any(page, self.selectors.two_factor_code()).await {
+                    self.submit_two_factor_code(page, code_input).await?;
+                    code_submitted = true;
+                    // Getting the code can take a while, like waiting for an email or a user prompt,
+                    // the verification gets its own wait so the code isn't reported as rejected too early
+                    deadline = Instant::now() + self.selectors.wait_timeout();
+                    continue;
                 }
-                if url == PINTEREST_LOGIN_URL {
+            }
+            check_login_challenges(page).await?;
+
+            // The login form shows an error next to the fields when the email or password is incorrect
+            if find_any(page, self.selectors.login_error()).await.is_some() {
+                #[cfg(feature = "debug")]
+                debug!("The login form shows a credentials error");
+                return Err(PinterestLoginError::AuthenticationError);
+            }
+
+            if Instant::now() >= deadline {
+                // The verification form is still shown when the code is wrong
+                if code_submitted && find_any(page, self.selectors.two_factor_code()).await.is_some() {
                     #[cfg(feature = "debug")]
-                    debug!("The url is the same as the login url, the login was unsuccessful");
-                    // If the url is the same as the login url, then the login was unsuccessful
-                    Err(PinterestLoginError::AuthenticationError)
-                } else {
+                    debug!("The two-factor code was rejected");
+                    return Err(PinterestLoginError::TwoFactorCodeRejected);
+                }
+                // The login form is still shown when the credentials were not accepted
+                if find_any(page, self.selectors.password()).await.is_some() {
                     #[cfg(feature = "debug")]
-                    info!("The url is not the same as the login url, the login was successful");
-                    Ok(())
+                    debug!("The success condition {:?} was not met, the login was unsuccessful", self.selectors.success());
+                    return Err(PinterestLoginError::AuthenticationError);
                 }
+                let url = page.url().await?.unwrap_or_default();
+                #[cfg(feature = "debug")]
+                debug!("The login landed on an unexpected page: {}", url);
+                return Err(PinterestLoginError::UnexpectedPage(url));
             }
+            runtime::sleep(POLL_INTERVAL).await;
         }
     }
 }
@@ -0,0 +1,356 @@
+use std::time::{Duration, Instant};
+use chromiumoxide::cdp::browser_protocol::target::{GetTargetsParams, TargetId, TargetInfo};
+use chromiumoxide::{Browser, Page};
+#[cfg(feature = "debug")]
+use log::{debug, info, trace};
+
+use crate::login_bot::BrowserLoginBot;
+use crate::selectors::{find_any, wait_for_any, SuccessCondition, POLL_INTERVAL};
+use crate::{runtime, PinterestLoginError};
+
+/// The login bot of the "Continue with Google" button, for the accounts without a pinterest password
+///
+/// It clicks the button on the login page, fills the google credentials in the popup,
+/// and waits for the popup to bring the session back to pinterest
+///
+/// # Example
+/// ```no_run
+/// use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+/// use pinterest_login::login;
+/// use pinterest_login::oauth::GoogleLoginBot;
+///
+/// # async fn run() -> pinterest_login::Result<()> {
+/// let bot = GoogleLoginBot::new("google email", "google password");
+/// let cookies = login(&bot, &DefaultBrowserConfigBuilder::default()).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct GoogleLoginBot<'a> {
+    flow: OAuthFlow<'a>,
+}
+
+impl<'a> GoogleLoginBot<'a> {
+    /// Creates a new google login bot
+    ///
+    /// # Arguments
+    /// * `email` - The email of the google account
+    /// * `password` - The password of the google account
+    pub fn new(email: &'a str, password: &'a str) -> Self {
+        Self { flow: OAuthFlow::new(&GOOGLE, email, password) }
+    }
+
+    /// Sets how long to wait for the popup, for the elements of the google login, and for the login to succeed
+    pub fn with_wait_timeout(mut self, wait_timeout: Duration) -> Self {
+        self.flow.wait_timeout = wait_timeout;
+        self
+    }
+
+    /// Sets the condition that tells the login was successful, the `_auth=1` cookie by default
+    pub fn with_success(mut self, success: SuccessCondition) -> Self {
+        self.flow.success = success;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl BrowserLoginBot for GoogleLoginBot<'_> {
+    async fn fill_login_form(&self, page: &Page) -> crate::Result<()> {
+        self.flow.open_provider(page).await
+    }
+
+    async fn submit_login_form(&self, page: &Page) -> crate::Result<()> {
+        self.flow.fill_provider(page).await
+    }
+
+    async fn check_login(&self, page: &Page) -> crate::Result<()> {
+        self.flow.check_login(page, None).await
+    }
+
+    async fn login(&self, browser: &Browser, page: &Page) -> crate::Result<()> {
+        self.flow.login(browser, page).await
+    }
+}
+
+/// The login bot of the "Continue with Facebook" button, for the accounts without a pinterest password
+///
+/// It clicks the button on the login page, fills the facebook credentials in the popup,
+/// and waits for the popup to bring the session back to pinterest
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use pinterest_login::oauth::FacebookLoginBot;
+///
+/// let bot = FacebookLoginBot::new("facebook email", "facebook password")
+///     .with_wait_timeout(Duration::from_secs(30));
+/// ```
+pub struct FacebookLoginBot<'a> {
+    flow: OAuthFlow<'a>,
+}
+
+impl<'a> FacebookLoginBot<'a> {
+    /// Creates a new facebook login bot
+    ///
+    /// # Arguments
+    /// * `email` - The email or the phone number of the facebook account
+    /// * `password` - The password of the facebook account
+    pub fn new(email: &'a str, password: &'a str) -> Self {
+        Self { flow: OAuthFlow::new(&FACEBOOK, email, password) }
+    }
+
+    /// Sets how long to wait for the popup, for the elements of the facebook login, and for the login to succeed
+    pub fn with_wait_timeout(mut self, wait_timeout: Duration) -> Self {
+        self.flow.wait_timeout = wait_timeout;
+        self
+    }
+
+    /// Sets the condition that tells the login was successful, the `_auth=1` cookie by default
+    pub fn with_success(mut self, success: SuccessCondition) -> Self {
+        self.flow.success = success;
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl BrowserLoginBot for FacebookLoginBot<'_> {
+    async fn fill_login_form(&self, page: &Page) -> crate::Result<()> {
+        self.flow.open_provider(page).await
+    }
+
+    async fn submit_login_form(&self, page: &Page) -> crate::Result<()> {
+        self.flow.fill_provider(page).await
+    }
+
+    async fn check_login(&self, page: &Page) -> crate::Result<()> {
+        self.flow.check_login(page, None).await
+    }
+
+    async fn login(&self, browser: &Browser, page: &Page) -> crate::Result<()> {
+        self.flow.login(browser, page).await
+    }
+}
+
+/// The selectors of an OAuth provider, every field has candidate selectors tried in order
+struct Provider {
+    name: &'static str,
+    /// The button of the provider on the pinterest login page
+    button: &'static [&'static str],
+    email: &'static [&'static str],
+    /// The button that shows the password input, for the providers that ask the email first
+    email_next: &'static [&'static str],
+    password: &'static [&'static str],
+    submit: &'static [&'static str],
+    /// The button that grants pinterest access to the account, on the first login
+    consent: &'static [&'static str],
+}
+
+const GOOGLE: Provider = Provider {
+    name: "google",
+    button: &["div[data-test-id='google-connect-button']", "iframe[src*='accounts.google.com/gsi/button']"],
+    email: &["input#identifierId", "input[type='email']"],
+    email_next: &["#identifierNext button", "#identifierNext"],
+    password: &["input[name='Passwd']", "input[type='password']"],
+    submit: &["#passwordNext button", "#passwordNext"],
+    consent: &["#submit_approve_access button", "#submit_approve_access", "#confirm_yes"],
+};
+
+const FACEBOOK: Provider = Provider {
+    name: "facebook",
+    button: &["div[data-test-id='facebook-connect-button']", "button[data-test-id='facebook-connect-button']"],
+    email: &["input#email", "input[name='email']"],
+    email_next: &[],
+    password: &["input#pass", "input[name='pass']"],
+    submit: &["button[name='login']", "#loginbutton"],
+    consent: &["button[name='__CONFIRM__']", "div[aria-label^='Continue as']"],
+};
+
+/// The OAuth login flow shared by the provider login bots
+struct OAuthFlow<'a> {
+    provider: &'static Provider,
+    email: &'a str,
+    password: &'a str,
+    wait_timeout: Duration,
+    success: SuccessCondition,
+}
+
+impl<'a> OAuthFlow<'a> {
+    fn new(provider: &'static Provider, email: &'a str, password: &'a str) -> Self {
+        Self {
+            provider,
+            email,
+            password,
+            wait_timeout: Duration::from_secs(20),
+            success: SuccessCondition::cookie("_auth", "1"),
+        }
+    }
+
+    /// Clicks the provider button, follows the popup it opens, fills the provider credentials,
+    /// and waits for the session to come back to the pinterest page, the popup is closed afterwards
+    async fn login(&self, browser: &Browser, page: &Page) -> crate::Result<()> {
+        self.open_provider(page).await?;
+        let provider_page = self.wait_for_provider_page(browser, page).await?;
+
+        let result = match self.fill_provider(&provider_page).await {
+            Ok(()) => self.check_login(page, Some(&provider_page)).await,
+            Err(e) => Err(e),
+        };
+
+        if provider_page.target_id() != page.target_id() {
+            // The provider usually closes the popup itself once the access is granted
+            #[cfg(feature = "debug")]
+            debug!("Closing the {} popup", self.provider.name);
+            let _ = provider_page.close().await;
+        }
+        result
+    }
+
+    /// Clicks the provider button on the pinterest login page
+    async fn open_provider(&self, page: &Page) -> crate::Result<()> {
+        #[cfg(feature = "debug")]
+        info!("Clicking the continue with {} button", self.provider.name);
+        wait_for_any(page, &to_strings(self.provider.button), self.wait_timeout).await?.click().await?;
+        Ok(())
+    }
+
+    /// Waits for the popup opened by the provider button, or for the pinterest page to redirect to the provider
+    ///
+    /// The popup is the page target opened by the login page, other tabs of the browser are ignored
+    async fn wait_for_provider_page(&self, browser: &Browser, page: &Page) -> crate::Result<Page> {
+        let deadline = Instant::now() + self.wait_timeout;
+        loop {
+            let targets = browser.execute(GetTargetsParams::default()).await?.result.target_infos;
+            if let Some(popup_id) = find_popup(&targets, page.target_id()) {
+                // The page is only known once chromiumoxide attached to the new target
+                if let Ok(popup) = browser.get_page(popup_id.clone()).await {
+                    #[cfg(feature = "debug")]
+                    debug!("The {} login opened in a popup", self.provider.name);
+                    return Ok(popup);
+                }
+            }
+            if page.url().await?.is_some_and(|url| url.contains(self.provider.name)) {
+                #[cfg(feature = "debug")]
+                debug!("The {} login opened in the same window", self.provider.name);
+                return Ok(page.clone());
+            }
+            if Instant::now() >= deadline {
+                return Err(PinterestLoginError::OAuthWindowNotFound(self.provider.name.to_string()));
+            }
+            runtime::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Fills and submits the provider credentials
+    async fn fill_provider(&self, page: &Page) -> crate::Result<()> {
+        #[cfg(feature = "debug")] {
+            info!("Filling the {} login form", self.provider.name);
+            trace!("Filling the {} login form with the email: {}", self.provider.name, self.email);
+        }
+        wait_for_any(page, &to_strings(self.provider.email), self.wait_timeout).await?
+            .click().await?
+            .type_str(self.email).await?;
+        if !self.provider.email_next.is_empty() {
+            wait_for_any(page, &to_strings(self.provider.email_next), self.wait_timeout).await?.click().await?;
+        }
+        wait_for_any(page, &to_strings(self.provider.password), self.wait_timeout).await?
+            .click().await?
+            .type_str(self.password).await?;
+        wait_for_any(page, &to_strings(self.provider.submit), self.wait_timeout).await?.click().await?;
+        Ok(())
+    }
+
+    /// Waits for the success condition on the pinterest page,
+    /// and grants the access on the provider page if it asks for it
+    async fn check_login(&self, page: &Page, provider_page: Option<&Page>) -> crate::Result<()> {
+        let provider_page = provider_page.unwrap_or(page);
+        let deadline = Instant::now() + self.wait_timeout;
+        let mut consent_given = false;
+        loop {
+            if self.success.is_met(page).await? {
+                #[cfg(feature = "debug")]
+                info!("The {} login was successful", self.provider.name);
+                return Ok(());
+            }
+
+            // The popup is closed once the access is granted, so the lookup fails silently from then on
+            if !consent_given {
+                if let Some(consent) = find_any(provider_page, &to_strings(self.provider.consent)).await {
+                    #[cfg(feature = "debug")]
+                    debug!("Granting pinterest the access to the {} account", self.provider.name);
+                    consent.click().await?;
+                    consent_given = true;
+                }
+            }
+
+            if Instant::now() >= deadline {
+                // The popup is gone once it closed itself, the pinterest page tells where the login stopped then
+                let url = match provider_page.url().await {
+                    Ok(Some(url)) => url,
+                    _ => page.url().await?.unwrap_or_default(),
+                };
+                #[cfg(feature = "debug")]
+                debug!("The {} login didn't complete within {:?}, it stopped on: {}", self.provider.name, self.wait_timeout, url);
+                return Err(PinterestLoginError::UnexpectedPage(url));
+            }
+            runtime::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Returns the page target opened by the given page, the most recent one if it opened several
+fn find_popup<'t>(targets: &'t [TargetInfo], opener: &TargetId) -> Option<&'t TargetId> {
+    targets.iter()
+        .rev()
+        .find(|target| target.r#type == "page" && target.opener_id.as_ref() == Some(opener))
+        .map(|target| &target.target_id)
+}
+
+fn to_strings(selectors: &[&str]) -> Vec<String> {
+    selectors.iter().map(|selector| selector.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(id: &str, r#type: &str, opener: Option<&str>) -> TargetInfo {
+        TargetInfo {
+            target_id: TargetId::new(id),
+            r#type: r#type.to_string(),
+            title: String::new(),
+            url: String::new(),
+            attached: false,
+            opener_id: opener.map(TargetId::new),
+            can_access_opener: false,
+            opener_frame_id: None,
+            browser_context_id: None,
+        }
+    }
+
+    #[test]
+    fn find_popup_only_returns_the_pages_opened_by_the_login_page() {
+        let login_page = TargetId::new("login");
+        let targets = [
+            target("login", "page", None),
+            // Another tab, opened by the user or by another login running in the same browser
+            target("other", "page", None),
+            target("other-popup", "page", Some("other")),
+            target("worker", "service_worker", Some("login")),
+        ];
+        assert_eq!(find_popup(&targets, &login_page), None);
+
+        let mut targets = targets.to_vec();
+        targets.push(target("popup", "page", Some("login")));
+        assert_eq!(find_popup(&targets, &login_page), Some(&TargetId::new("popup")));
+    }
+
+    #[test]
+    fn find_popup_returns_the_latest_popup() {
+        // The provider button was clicked twice, the first popup is stale
+        let targets = [
+            target("login", "page", None),
+            target("first", "page", Some("login")),
+            target("second", "page", Some("login")),
+        ];
+        assert_eq!(find_popup(&targets, &TargetId::new("login")), Some(&TargetId::new("second")));
+    }
+}
@@ -0,0 +1,64 @@
+use std::future::Future;
+use std::time::Duration;
+use chromiumoxide::Handler;
+use futures::future::{self, AbortHandle, Abortable, Either};
+use futures::{FutureExt, StreamExt};
+
+use crate::spawner::Spawner;
+
+/// Waits for the given duration, with the tokio timer when called from a tokio runtime, then with the async-std timer,
+/// or with a helper thread when no runtime feature is enabled
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "tokio-runtime")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return tokio::time::sleep(duration).await;
+    }
+
+    #[cfg(feature = "async-std-runtime")]
+    return async_std::task::sleep(duration).await;
+
+    #[cfg(not(feature = "async-std-runtime"))]
+    {
+        let (sender, receiver) = futures::channel::oneshot::channel::<()>();
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            let _ = sender.send(());
+        });
+        let _ = receiver.await;
+    }
+}
+
+/// Runs the future until it completes or the timeout elapses, returns `None` on timeout
+pub(crate) async fn timeout<F: Future>(duration: Duration, fut: F) -> Option<F::Output> {
+    match future::select(Box::pin(fut), Box::pin(sleep(duration))).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
+
+/// The task that drives the chromiumoxide handler, it stops by itself when the browser connection closes
+pub(crate) struct HandlerTask {
+    abort_handle: AbortHandle,
+}
+
+/// Spawns the task that drives the chromiumoxide handler with the spawner
+pub(crate) fn spawn_handler(mut handler: Handler, spawner: &dyn Spawner) -> HandlerTask {
+    let drive = async move {
+        while let Some(event) = handler.next().await {
+            if event.is_err() {
+                break;
+            }
+        }
+    };
+
+    let (abort_handle, registration) = AbortHandle::new_pair();
+    spawner.spawn(Abortable::new(drive, registration).map(|_| ()).boxed());
+    HandlerTask { abort_handle }
+}
+
+impl HandlerTask {
+    /// Cancels the task
+    pub(crate) fn cancel(self) {
+        self.abort_handle.abort();
+    }
+}
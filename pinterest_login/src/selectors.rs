@@ -0,0 +1,344 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chromiumoxide::{Element, Page};
+#[cfg(feature = "debug")]
+use log::{debug, trace};
+
+use crate::{runtime, PinterestLoginError};
+
+/// How often the page is checked while waiting for an element or for the success condition
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The selectors and the wait conditions of the default login bot
+///
+/// Every field has several candidate selectors, tried in order, so a redesign of the login page
+/// can be handled by adding the new selector without waiting for a new release
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use pinterest_login::login_bot::DefaultBrowserLoginBot;
+/// use pinterest_login::selectors::{LoginSelectors, SuccessCondition};
+///
+/// let selectors = LoginSelectors::default()
+///     .with_email(["input#email", "input[name='id']"])
+///     .with_wait_timeout(Duration::from_secs(20))
+///     .with_success(SuccessCondition::element(["div[data-test-id='header-profile']"]));
+/// let bot = DefaultBrowserLoginBot::new("email", "password").with_selectors(selectors);
+/// ```
+#[derive(Debug, Clone)]
+pub struct LoginSelectors {
+    email: Vec<String>,
+    password: Vec<String>,
+    submit: Vec<String>,
+    two_factor_code: Vec<String>,
+    login_error: Vec<String>,
+    wait_timeout: Duration,
+    success: SuccessCondition,
+}
+
+impl Default for LoginSelectors {
+    /// Creates the selectors of the current pinterest login page, with a 10 seconds wait timeout,
+    /// the login is successful when the `_auth=1` cookie is set
+    fn default() -> Self {
+        Self {
+            email: to_strings(["input#email", "input[name='id']", "input[type='email']"]),
+            password: to_strings(["input#password", "input[name='password']", "input[type='password']"]),
+            submit: to_strings(["button[type='submit']", "div[data-test-id='registerFormSubmitButton'] button"]),
+            two_factor_code: to_strings(["input[autocomplete='one-time-code']", "input[name='code']"]),
+            login_error: to_strings(["[data-test-id='login-error']", "#email-error", "#password-error"]),
+            wait_timeout: Duration::from_secs(10),
+            success: SuccessCondition::cookie("_auth", "1"),
+        }
+    }
+}
+
+impl LoginSelectors {
+    /// Sets the candidate selectors of the email input
+    pub fn with_email<I: IntoIterator<Item = S>, S: Into<String>>(mut self, selectors: I) -> Self {
+        self.email = to_strings(selectors);
+        self
+    }
+
+    /// Sets the candidate selectors of the password input
+    pub fn with_password<I: IntoIterator<Item = S>, S: Into<String>>(mut self, selectors: I) -> Self {
+        self.password = to_strings(selectors);
+        self
+    }
+
+    /// Sets the candidate selectors of the submit button, of the login form and of the two-factor form
+    pub fn with_submit<I: IntoIterator<Item = S>, S: Into<String>>(mut self, selectors: I) -> Self {
+        self.submit = to_strings(selectors);
+        self
+    }
+
+    /// Sets the candidate selectors of the two-factor code input
+    pub fn with_two_factor_code<I: IntoIterator<Item = S>, S: Into<String>>(mut self, selectors: I) -> Self {
+        self.two_factor_code = to_strings(selectors);
+        self
+    }
+
+    /// Sets the candidate selectors of the error shown on the login form when the email or password is incorrect
+    pub fn with_login_error<I: IntoIterator<Item = S>, S: Into<String>>(mut self, selectors: I) -> Self {
+        self.login_error = to_strings(selectors);
+        self
+    }
+
+    /// Sets how long to wait for an element to appear, and for the success condition to be met
+    pub fn with_wait_timeout(mut self, wait_timeout: Duration) -> Self {
+        self.wait_timeout = wait_timeout;
+        self
+    }
+
+    /// Sets the condition that tells the login was successful
+    pub fn with_success(mut self, success: SuccessCondition) -> Self {
+        self.success = success;
+        self
+    }
+
+    /// Returns the candidate selectors of the email input
+    pub fn email(&self) -> &[String] {
+        &self.email
+    }
+
+    /// Returns the candidate selectors of the password input
+    pub fn password(&self) -> &[String] {
+        &self.password
+    }
+
+    /// Returns the candidate selectors of the submit button
+    pub fn submit(&self) -> &[String] {
+        &self.submit
+    }
+
+    /// Returns the candidate selectors of the two-factor code input
+    pub fn two_factor_code(&self) -> &[String] {
+        &self.two_factor_code
+    }
+
+    /// Returns the candidate selectors of the login form error
+    pub fn login_error(&self) -> &[String] {
+        &self.login_error
+    }
+
+    /// Returns the wait timeout
+    pub fn wait_timeout(&self) -> Duration {
+        self.wait_timeout
+    }
+
+    /// Returns the success condition
+    pub fn success(&self) -> &SuccessCondition {
+        &self.success
+    }
+}
+
+/// The condition that tells the login was successful
+///
+/// # Example
+/// ```
+/// use pinterest_login::selectors::SuccessCondition;
+///
+/// // Either the auth cookie is set, or the settings of the user can be fetched
+/// let success = SuccessCondition::any([
+///     SuccessCondition::cookie("_auth", "1"),
+///     SuccessCondition::session_endpoint("https://www.pinterest.com/resource/UserSettingsResource/get/"),
+/// ]);
+/// ```
+#[derive(Clone)]
+pub enum SuccessCondition {
+    /// The url of the page matches the predicate
+    Url(Arc<dyn Fn(&str) -> bool + Send + Sync>),
+    /// One of the elements is on the page
+    Element(Vec<String>),
+    /// The page has the cookie, with the value if it's set
+    Cookie {
+        /// The name of the cookie
+        name: String,
+        /// The expected value of the cookie, any value matches if it's `None`
+        value: Option<String>,
+    },
+    /// A request to the endpoint, sent from the page with its cookies, is successful
+    SessionEndpoint(String),
+    /// One of the conditions is met
+    Any(Vec<SuccessCondition>),
+}
+
+impl SuccessCondition {
+    /// The login is successful when the url of the page matches the predicate
+    pub fn url<F: Fn(&str) -> bool + Send + Sync + 'static>(predicate: F) -> Self {
+        Self::Url(Arc::new(predicate))
+    }
+
+    /// The login is successful when one of the elements is on the page
+    pub fn element<I: IntoIterator<Item = S>, S: Into<String>>(selectors: I) -> Self {
+        Self::Element(to_strings(selectors))
+    }
+
+    /// The login is successful when the page has the cookie with the value
+    pub fn cookie<N: Into<String>, V: Into<String>>(name: N, value: V) -> Self {
+        Self::Cookie { name: name.into(), value: Some(value.into()) }
+    }
+
+    /// The login is successful when a request to the endpoint, sent from the page with its cookies,
+    /// returns a 2xx status
+    pub fn session_endpoint<S: Into<String>>(url: S) -> Self {
+        Self::SessionEndpoint(url.into())
+    }
+
+    /// The login is successful when one of the conditions is met
+    pub fn any<I: IntoIterator<Item = SuccessCondition>>(conditions: I) -> Self {
+        Self::Any(conditions.into_iter().collect())
+    }
+
+    /// Returns true if the condition is met now
+    pub async fn is_met(&self, page: &Page) -> crate::Result<bool> {
+        Ok(match self {
+            Self::Url(predicate) => page.url().await?.is_some_and(|url| predicate(&url)),
+            Self::Element(selectors) => find_any(page, selectors).await.is_some(),
+            Self::Cookie { name, value } => page.get_cookies().await?
+                .iter()
+                .any(|cookie| &cookie.name == name && value.as_ref().is_none_or(|value| &cookie.value == value)),
+            Self::SessionEndpoint(url) => {
+                let url = serde_json::to_string(url).unwrap_or_default();
+                // A failed request, like a navigation in progress, means the session is not there yet
+                page.evaluate(format!("fetch({url}, {{ credentials: 'include' }}).then(r => r.ok).catch(() => false)"))
+                    .await
+                    .ok()
+                    .and_then(|result| result.into_value::<bool>().ok())
+                    .unwrap_or(false)
+            }
+            Self::Any(conditions) => {
+                for condition in conditions {
+                    // Box the recursive future
+                    if Box::pin(condition.is_met(page)).await? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+        })
+    }
+
+    /// Waits until the condition is met, returns false if it's not met within the timeout
+    pub async fn wait(&self, page: &Page, timeout: Duration) -> crate::Result<bool> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.is_met(page).await? {
+                return Ok(true);
+            }
+            if Instant::now() >= deadline {
+                return Ok(false);
+            }
+            runtime::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+impl std::fmt::Debug for SuccessCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Url(_) => f.write_str("Url(<predicate>)"),
+            Self::Element(selectors) => f.debug_tuple("Element").field(selectors).finish(),
+            Self::Cookie { name, value } => f.debug_struct("Cookie").field("name", name).field("value", value).finish(),
+            Self::SessionEndpoint(url) => f.debug_tuple("SessionEndpoint").field(url).finish(),
+            Self::Any(conditions) => f.debug_tuple("Any").field(conditions).finish(),
+        }
+    }
+}
+
+/// Returns the first element matching one of the selectors, tried in order, without waiting
+pub async fn find_any(page: &Page, selectors: &[String]) -> Option<Element> {
+    for selector in selectors {
+        if let Ok(element) = page.find_element(selector.as_str()).await {
+            #[cfg(feature = "debug")]
+            trace!("Found the element with the selector: {}", selector);
+            return Some(element);
+        }
+    }
+    None
+}
+
+/// Waits for an element matching one of the selectors, tried in order
+///
+/// # Errors
+/// * `SelectorNotFound` - If no element matches within the timeout
+pub async fn wait_for_any(page: &Page, selectors: &[String], timeout: Duration) -> crate::Result<Element> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(element) = find_any(page, selectors).await {
+            return Ok(element);
+        }
+        if Instant::now() >= deadline {
+            #[cfg(feature = "debug")]
+            debug!("None of the selectors matched within {:?}: {:?}", timeout, selectors);
+            return Err(PinterestLoginError::SelectorNotFound(selectors.join(", ")));
+        }
+        runtime::sleep(POLL_INTERVAL).await;
+    }
+}
+
+fn to_strings<I: IntoIterator<Item = S>, S: Into<String>>(selectors: I) -> Vec<String> {
+    selectors.into_iter().map(Into::into).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_selectors_match_the_login_page() {
+        let selectors = LoginSelectors::default();
+        assert_eq!(selectors.email()[0], "input#email");
+        assert_eq!(selectors.password()[0], "input#password");
+        assert_eq!(selectors.submit()[0], "button[type='submit']");
+        assert!(!selectors.two_factor_code().is_empty());
+        assert!(!selectors.login_error().is_empty());
+        assert_eq!(selectors.wait_timeout(), Duration::from_secs(10));
+        assert!(matches!(selectors.success(),
+                         SuccessCondition::Cookie { name, value: Some(value) } if name == "_auth" && value == "1"));
+    }
+
+    #[test]
+    fn the_selectors_are_replaced_in_order() {
+        let selectors = LoginSelectors::default()
+            .with_email(["input[name='id']", "input#email"])
+            .with_password(vec!["input#pass".to_string()])
+            .with_submit(["button.login"])
+            .with_two_factor_code(["input#code"])
+            .with_login_error(["div.error"])
+            .with_wait_timeout(Duration::from_secs(30))
+            .with_success(SuccessCondition::element(["div.profile"]));
+
+        assert_eq!(selectors.email(), ["input[name='id']", "input#email"]);
+        assert_eq!(selectors.password(), ["input#pass"]);
+        assert_eq!(selectors.submit(), ["button.login"]);
+        assert_eq!(selectors.two_factor_code(), ["input#code"]);
+        assert_eq!(selectors.login_error(), ["div.error"]);
+        assert_eq!(selectors.wait_timeout(), Duration::from_secs(30));
+        assert!(matches!(selectors.success(), SuccessCondition::Element(selectors) if selectors == &["div.profile"]));
+    }
+
+    #[test]
+    fn the_success_conditions_are_built_and_combined() {
+        let condition = SuccessCondition::any([
+            SuccessCondition::url(|url| url.ends_with("/homefeed/")),
+            SuccessCondition::cookie("_auth", "1"),
+            SuccessCondition::session_endpoint("https://www.pinterest.com/resource/UserSettingsResource/get/"),
+            SuccessCondition::any([]),
+        ]);
+
+        let SuccessCondition::Any(conditions) = &condition else {
+            panic!("unexpected condition: {condition:?}");
+        };
+        assert_eq!(conditions.len(), 4);
+        let SuccessCondition::Url(predicate) = &conditions[0] else {
+            panic!("unexpected condition: {:?}", conditions[0]);
+        };
+        assert!(predicate("https://www.pinterest.com/homefeed/"));
+        assert!(!predicate("https://www.pinterest.com/login/"));
+        assert!(matches!(&conditions[3], SuccessCondition::Any(conditions) if conditions.is_empty()));
+
+        assert_eq!(format!("{condition:?}"), "Any([Url(<predicate>), Cookie { name: \"_auth\", value: Some(\"1\") }, \
+            SessionEndpoint(\"https://www.pinterest.com/resource/UserSettingsResource/get/\"), Any([])])");
+    }
+}
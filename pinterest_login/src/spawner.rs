@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use futures::future::BoxFuture;
+
+/// Spawns the background task that drives the browser connection during the login
+///
+/// The runtime features provide [`AsyncStdSpawner`] and [`TokioSpawner`], and [`ThreadSpawner`] works everywhere.
+/// It's implemented for the closures that take the task, so any executor can be plugged in with [`crate::LoginOptions::with_spawner`]
+///
+/// # Example
+/// ```no_run
+/// use pinterest_login::LoginOptions;
+///
+/// // Spawn on the executor of the application, like smol
+/// let options = LoginOptions::new().with_spawner(|task| {
+///     std::thread::spawn(move || futures::executor::block_on(task));
+/// });
+/// ```
+pub trait Spawner: Send + Sync {
+    /// Spawns the task in the background, it stops by itself when the browser connection closes,
+    /// and it's aborted once the login is done
+    fn spawn(&self, task: BoxFuture<'static, ()>);
+}
+
+impl<F: Fn(BoxFuture<'static, ()>) + Send + Sync> Spawner for F {
+    #[inline]
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        self(task)
+    }
+}
+
+impl std::fmt::Debug for dyn Spawner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Spawner")
+    }
+}
+
+/// Spawns the task on the async-std runtime
+#[cfg(feature = "async-std-runtime")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsyncStdSpawner;
+
+#[cfg(feature = "async-std-runtime")]
+impl Spawner for AsyncStdSpawner {
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        async_std::task::spawn(task);
+    }
+}
+
+/// Spawns the task on the current tokio runtime, it panics outside of a tokio runtime
+#[cfg(feature = "tokio-runtime")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioSpawner;
+
+#[cfg(feature = "tokio-runtime")]
+impl Spawner for TokioSpawner {
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        tokio::spawn(task);
+    }
+}
+
+/// Runs the task on its own thread, it doesn't depend on any runtime
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadSpawner;
+
+impl Spawner for ThreadSpawner {
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        std::thread::spawn(move || futures::executor::block_on(task));
+    }
+}
+
+/// Returns the spawner used when none is set in the login options
+///
+/// It's [`TokioSpawner`] when called from a tokio runtime, then [`AsyncStdSpawner`],
+/// and [`ThreadSpawner`] when no runtime feature is enabled
+pub fn default_spawner() -> Arc<dyn Spawner> {
+    #[cfg(feature = "tokio-runtime")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return Arc::new(TokioSpawner);
+    }
+
+    #[cfg(feature = "async-std-runtime")]
+    return Arc::new(AsyncStdSpawner);
+
+    #[cfg(not(feature = "async-std-runtime"))]
+    Arc::new(ThreadSpawner)
+}
@@ -0,0 +1,202 @@
+use chromiumoxide::cdp::browser_protocol::emulation::{SetDeviceMetricsOverrideParams, SetUserAgentOverrideParams};
+use chromiumoxide::Page;
+#[cfg(feature = "debug")]
+use log::{debug, trace};
+
+/// The options of the stealth mode, that hides the usual signs of an automated headless browser
+///
+/// The evasion scripts run in every frame before the page scripts, they hide `navigator.webdriver`,
+/// fill `navigator.plugins`, `navigator.languages` and `window.chrome`, and report a real WebGL vendor
+/// for the platform of the user agent.
+/// The user agent, the `Accept-Language` header and the viewport are overridden too
+///
+/// # Example
+/// ```no_run
+/// use pinterest_login::LoginOptions;
+/// use pinterest_login::stealth::StealthOptions;
+///
+/// let options = LoginOptions::new()
+///     .with_stealth(StealthOptions::default().with_viewport(1440, 900));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StealthOptions {
+    user_agent: Option<String>,
+    languages: Vec<String>,
+    viewport: (u32, u32),
+}
+
+impl Default for StealthOptions {
+    /// Creates the default stealth options, with the browser user agent without `HeadlessChrome`,
+    /// the `en-US` and `en` languages, and a 1366x768 viewport
+    fn default() -> Self {
+        Self {
+            user_agent: None,
+            languages: vec!["en-US".to_string(), "en".to_string()],
+            viewport: (1366, 768),
+        }
+    }
+}
+
+impl StealthOptions {
+    /// Sets the user agent, use the same one as the api client so the session looks like a single browser
+    pub fn with_user_agent<S: Into<String>>(mut self, user_agent: S) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Returns the user agent, `None` means the browser one without `HeadlessChrome`
+    pub fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_deref()
+    }
+
+    /// Sets the preferred languages, like `["fr-FR", "fr"]`
+    pub fn with_languages<I: IntoIterator<Item = S>, S: Into<String>>(mut self, languages: I) -> Self {
+        self.languages = languages.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the viewport size, in pixels
+    pub fn with_viewport(mut self, width: u32, height: u32) -> Self {
+        self.viewport = (width, height);
+        self
+    }
+
+    /// Applies the stealth mode to the page, it has to be called before navigating
+    pub(crate) async fn apply(&self, page: &Page) -> crate::Result<()> {
+        let user_agent = match &self.user_agent {
+            Some(user_agent) => user_agent.clone(),
+            None => page.user_agent().await?.replace("HeadlessChrome", "Chrome"),
+        };
+        let accept_language = accept_language(&self.languages);
+        let (webgl_vendor, webgl_renderer) = webgl(&user_agent);
+
+        #[cfg(feature = "debug")] {
+            debug!("Applying the stealth mode");
+            trace!("User agent: {}", user_agent);
+            trace!("Accept language: {}", accept_language);
+            trace!("Viewport: {:?}", self.viewport);
+        }
+
+        page.execute(SetUserAgentOverrideParams {
+            accept_language: Some(accept_language),
+            platform: Some(platform(&user_agent).to_string()),
+            ..SetUserAgentOverrideParams::new(user_agent)
+        }).await?;
+        page.execute(SetDeviceMetricsOverrideParams::new(self.viewport.0, self.viewport.1, 1.0, false)).await?;
+
+        let languages = serde_json::to_string(&self.languages).unwrap_or_else(|_| "[]".to_string());
+        let script = EVASIONS_SCRIPT
+            .replace("__LANGUAGES__", &languages)
+            .replace("__WEBGL_VENDOR__", &serde_json::Value::from(webgl_vendor).to_string())
+            .replace("__WEBGL_RENDERER__", &serde_json::Value::from(webgl_renderer).to_string());
+        page.evaluate_on_new_document(script).await?;
+
+        Ok(())
+    }
+}
+
+/// Builds the `Accept-Language` header from the languages, with decreasing quality values
+fn accept_language(languages: &[String]) -> String {
+    languages.iter()
+        .enumerate()
+        .map(|(i, language)| match i {
+            0 => language.clone(),
+            _ => format!("{language};q={:.1}", (10 - i.min(9)) as f32 / 10.0),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Returns the `navigator.platform` matching the user agent
+fn platform(user_agent: &str) -> &'static str {
+    if user_agent.contains("Windows") {
+        "Win32"
+    } else if user_agent.contains("Macintosh") {
+        "MacIntel"
+    } else {
+        "Linux x86_64"
+    }
+}
+
+/// Returns the unmasked WebGL vendor and renderer of a common Intel gpu on the platform of the user agent,
+/// as reported by chrome through ANGLE
+fn webgl(user_agent: &str) -> (&'static str, &'static str) {
+    match platform(user_agent) {
+        "Win32" => ("Google Inc. (Intel)", "ANGLE (Intel, Intel(R) UHD Graphics 630 (0x00003E9B) Direct3D11 vs_5_0 ps_5_0, D3D11)"),
+        "MacIntel" => ("Google Inc. (Intel Inc.)", "ANGLE (Intel Inc., Intel(R) Iris(TM) Plus Graphics OpenGL Engine, OpenGL 4.1)"),
+        _ => ("Google Inc. (Intel)", "ANGLE (Intel, Mesa Intel(R) UHD Graphics 620 (KBL GT2), OpenGL 4.6)"),
+    }
+}
+
+/// The evasion scripts, `__LANGUAGES__` is replaced with the json list of the languages,
+/// `__WEBGL_VENDOR__` and `__WEBGL_RENDERER__` with the json strings of the WebGL vendor and renderer
+const EVASIONS_SCRIPT: &str = r#"
+(() => {
+    Object.defineProperty(Navigator.prototype, 'webdriver', { get: () => undefined });
+
+    Object.defineProperty(Navigator.prototype, 'languages', { get: () => __LANGUAGES__ });
+
+    const plugins = ['Chrome PDF Plugin', 'Chrome PDF Viewer', 'Native Client'].map(name => ({ name, filename: name, description: name }));
+    Object.defineProperty(Navigator.prototype, 'plugins', { get: () => plugins });
+
+    window.chrome = window.chrome || { runtime: {}, app: {}, csi: () => {}, loadTimes: () => {} };
+
+    const query = window.navigator.permissions && window.navigator.permissions.query;
+    if (query) {
+        window.navigator.permissions.query = (parameters) => parameters.name === 'notifications'
+            ? Promise.resolve({ state: Notification.permission })
+            : query.call(window.navigator.permissions, parameters);
+    }
+
+    for (const context of [window.WebGLRenderingContext, window.WebGL2RenderingContext]) {
+        if (!context) continue;
+        const getParameter = context.prototype.getParameter;
+        context.prototype.getParameter = function (parameter) {
+            // UNMASKED_VENDOR_WEBGL and UNMASKED_RENDERER_WEBGL
+            if (parameter === 37445) return __WEBGL_VENDOR__;
+            if (parameter === 37446) return __WEBGL_RENDERER__;
+            return getParameter.call(this, parameter);
+        };
+    }
+})();
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOWS: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36";
+    const MACOS: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36";
+    const LINUX: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/141.0.0.0 Safari/537.36";
+
+    #[test]
+    fn platform_follows_the_user_agent() {
+        assert_eq!(platform(WINDOWS), "Win32");
+        assert_eq!(platform(MACOS), "MacIntel");
+        assert_eq!(platform(LINUX), "Linux x86_64");
+        assert_eq!(platform(""), "Linux x86_64");
+    }
+
+    #[test]
+    fn webgl_follows_the_platform() {
+        assert!(webgl(WINDOWS).1.contains("Direct3D11"));
+        assert!(webgl(MACOS).1.contains("OpenGL Engine"));
+        let (vendor, renderer) = webgl(LINUX);
+        assert_eq!(vendor, "Google Inc. (Intel)");
+        assert!(renderer.contains("Mesa") && !renderer.contains("Iris"));
+    }
+
+    #[test]
+    fn accept_language_decreases_the_quality() {
+        let languages = |languages: &[&str]| languages.iter().map(|language| language.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(accept_language(&languages(&["en-US", "en"])), "en-US,en;q=0.9");
+        assert_eq!(accept_language(&languages(&["fr-FR", "fr", "en-US", "en"])), "fr-FR,fr;q=0.9,en-US;q=0.8,en;q=0.7");
+        assert_eq!(accept_language(&languages(&["de"])), "de");
+        assert_eq!(accept_language(&[]), "");
+
+        // The quality doesn't go below 0.1
+        let many = accept_language(&languages(&["a"; 12]));
+        assert!(many.ends_with("a;q=0.1,a;q=0.1,a;q=0.1"), "{many}");
+    }
+}
@@ -0,0 +1,225 @@
+use std::future::Future;
+use std::time::{SystemTime, UNIX_EPOCH};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+#[cfg(feature = "debug")]
+use log::{debug, trace};
+
+use crate::PinterestLoginError;
+
+/// Provides the verification code when the account has two-factor authentication enabled
+///
+/// It's implemented for the async closures that return the code,
+/// and by [`TotpCodeProvider`] and [`StdinCodeProvider`]
+///
+/// # Example
+/// ```no_run
+/// # use pinterest_login::login_bot::DefaultBrowserLoginBot;
+///
+/// let bot = DefaultBrowserLoginBot::new("email", "password")
+///     .with_two_factor(|| async {
+///         // Ask the user, read it from an sms gateway, ...
+///         Ok("123456".to_string())
+///     });
+/// ```
+#[async_trait::async_trait]
+pub trait TwoFactorCodeProvider: Send + Sync {
+    /// Returns the current verification code
+    async fn code(&self) -> crate::Result<String>;
+}
+
+#[async_trait::async_trait]
+impl<F, Fut> TwoFactorCodeProvider for F
+    where F: Fn() -> Fut + Send + Sync, Fut: Future<Output = crate::Result<String>> + Send {
+    #[inline]
+    async fn code(&self) -> crate::Result<String> {
+        self().await
+    }
+}
+
+/// Generates the verification codes from the secret of an authenticator app (RFC 6238),
+/// the secret is the base32 string shown when setting up the authenticator
+///
+/// # Example
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use pinterest_login::two_factor::TotpCodeProvider;
+///
+/// // The test vector of RFC 6238
+/// let totp = TotpCodeProvider::new("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ")?;
+/// assert_eq!(totp.code_at(UNIX_EPOCH + Duration::from_secs(59)), "287082");
+/// # Ok::<(), pinterest_login::PinterestLoginError>(())
+/// ```
+#[derive(Clone)]
+pub struct TotpCodeProvider {
+    key: Vec<u8>,
+    digits: u32,
+    period: u64,
+}
+
+impl TotpCodeProvider {
+    /// Creates a new TOTP code provider, with 6 digits codes and a 30 seconds period
+    ///
+    /// # Arguments
+    /// * `secret` - The base32 secret, the case, the spaces and the padding are ignored
+    ///
+    /// # Errors
+    /// * `InvalidTotpSecret` - If the secret is not valid base32
+    pub fn new(secret: &str) -> crate::Result<Self> {
+        Ok(Self {
+            key: decode_base32(secret).ok_or(PinterestLoginError::InvalidTotpSecret)?,
+            digits: 6,
+            period: 30,
+        })
+    }
+
+    /// Sets the number of digits of the codes, between 6 and 8
+    pub fn with_digits(mut self, digits: u32) -> Self {
+        self.digits = digits.clamp(6, 8);
+        self
+    }
+
+    /// Sets the period of the codes in seconds
+    pub fn with_period(mut self, period: u64) -> Self {
+        self.period = period.max(1);
+        self
+    }
+
+    /// Returns the code at the given time
+    pub fn code_at(&self, time: SystemTime) -> String {
+        let counter = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) / self.period;
+
+        let mut mac = Hmac::<Sha1>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(&counter.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+
+        // The dynamic truncation of RFC 4226
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([hash[offset] & 0x7f, hash[offset + 1], hash[offset + 2], hash[offset + 3]]);
+
+        format!("{:0width$}", binary % 10u32.pow(self.digits), width = self.digits as usize)
+    }
+}
+
+impl std::fmt::Debug for TotpCodeProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TotpCodeProvider")
+            .field("digits", &self.digits)
+            .field("period", &self.period)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl TwoFactorCodeProvider for TotpCodeProvider {
+    async fn code(&self) -> crate::Result<String> {
+        #[cfg(feature = "debug")]
+        debug!("Generating the TOTP code");
+        Ok(self.code_at(SystemTime::now()))
+    }
+}
+
+/// Prompts for the verification code on the terminal, and reads it from the standard input
+#[derive(Debug, Clone, Default)]
+pub struct StdinCodeProvider {
+    prompt: Option<String>,
+}
+
+impl StdinCodeProvider {
+    /// Creates a new stdin code provider, with the default prompt
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the prompt that is printed to the standard error before reading the code
+    pub fn with_prompt<S: Into<String>>(mut self, prompt: S) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl TwoFactorCodeProvider for StdinCodeProvider {
+    async fn code(&self) -> crate::Result<String> {
+        use std::io::Write;
+
+        let prompt = self.prompt.clone().unwrap_or_else(|| "Enter the verification code: ".to_string());
+        let (sender, receiver) = futures::channel::oneshot::channel();
+
+        // Read in a separate thread, so the runtime is not blocked while waiting for the user
+        std::thread::spawn(move || {
+            eprint!("{prompt}");
+            let _ = std::io::stderr().flush();
+            let mut code = String::new();
+            let _ = sender.send(std::io::stdin().read_line(&mut code).map(|_| code));
+        });
+
+        let code = receiver.await
+            .map_err(|_| PinterestLoginError::TwoFactorCodeUnavailable("the stdin reader stopped".to_string()))?
+            .map_err(|e| PinterestLoginError::TwoFactorCodeUnavailable(e.to_string()))?;
+
+        #[cfg(feature = "debug")]
+        trace!("Read the verification code from stdin: {}", code.trim());
+        Ok(code.trim().to_string())
+    }
+}
+
+/// Decodes a base32 string (RFC 4648), returns `None` if it contains invalid characters
+fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(input.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0u32);
+
+    for c in input.chars().filter(|c| !c.is_whitespace() && *c != '=' && *c != '-') {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u32 - 'A' as u32,
+            c @ '2'..='7' => c as u32 - '2' as u32 + 26,
+            _ => return None,
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    (!bytes.is_empty()).then_some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_base32_reads_the_rfc_4648_test_vectors() {
+        for (encoded, decoded) in [("MY", "f"), ("MZXQ", "fo"), ("MZXW6", "foo"), ("MZXW6YQ", "foob"),
+                                   ("MZXW6YTB", "fooba"), ("MZXW6YTBOI", "foobar")] {
+            assert_eq!(decode_base32(encoded).as_deref(), Some(decoded.as_bytes()), "{encoded}");
+        }
+    }
+
+    #[test]
+    fn decode_base32_ignores_the_padding_the_case_and_the_separators() {
+        let foobar = Some(b"foobar".to_vec());
+        assert_eq!(decode_base32("MZXW6YTBOI======"), foobar);
+        assert_eq!(decode_base32("mzxw6ytboi"), foobar);
+        assert_eq!(decode_base32("MzXw6YtBoI"), foobar);
+        assert_eq!(decode_base32("mzxw 6ytb oi=="), foobar);
+        assert_eq!(decode_base32("MZXW-6YTB-OI"), foobar);
+        assert_eq!(decode_base32("MY======"), Some(b"f".to_vec()));
+    }
+
+    #[test]
+    fn decode_base32_rejects_the_invalid_characters() {
+        // 0, 1, 8 and 9 are not in the base32 alphabet, they are easily mistaken for O, I, B and g
+        for secret in ["MZXW0", "MZXW1", "MZXW8", "MZXW9", "MZXW6YTBOI!", "MZXW_6YTB", "MZXWÉ", "MZXW6YTBOI\u{0}"] {
+            assert_eq!(decode_base32(secret), None, "{secret:?}");
+        }
+        // Nothing to decode
+        for secret in ["", "   ", "====", "M"] {
+            assert_eq!(decode_base32(secret), None, "{secret:?}");
+        }
+        assert!(matches!(TotpCodeProvider::new("not base32!"), Err(PinterestLoginError::InvalidTotpSecret)));
+    }
+}
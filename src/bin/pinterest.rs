@@ -0,0 +1,407 @@
+//! The `pinterest` command line tool, to do one-off tasks without writing rust
+//!
+//! Build it with the `cli` feature: `cargo install pinterest_rs --features cli`
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use clap::{Parser, Subcommand, ValueEnum};
+use pinterest_login::config_builder::DefaultBrowserConfigBuilder;
+use pinterest_login::login_bot::DefaultBrowserLoginBot;
+use pinterest_login::human::InteractionOptions;
+use pinterest_login::LoginOptions;
+use pinterest_login::stealth::StealthOptions;
+use pinterest_login::two_factor::{StdinCodeProvider, TotpCodeProvider};
+use pinterest_rs::download::ImageSize;
+use pinterest_rs::mirror::MirrorOptions;
+use pinterest_rs::pin::Pin;
+use pinterest_rs::Pinterest;
+use serde_json::json;
+
+#[derive(Parser)]
+#[command(name = "pinterest", version, about = "Pinterest from the command line")]
+struct Cli {
+    /// The output format
+    #[arg(short, long, value_enum, global = true, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+    /// The user agent to send with the requests
+    #[arg(long, global = true)]
+    user_agent: Option<String>,
+    /// The session cookies file, written by `login` and read by the other commands
+    #[arg(long, global = true, env = "PINTEREST_COOKIES", default_value = "pinterest_cookies.json")]
+    cookies: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Logs in with a headless browser and saves the session cookies to a file
+    Login {
+        /// The account email
+        #[arg(long, env = "PINTEREST_EMAIL")]
+        email: String,
+        /// The account password
+        #[arg(long, env = "PINTEREST_PASSWORD", hide_env_values = true)]
+        password: String,
+        /// The base32 secret of the authenticator app, the code is asked on the terminal when missing
+        #[arg(long, env = "PINTEREST_TOTP_SECRET", hide_env_values = true)]
+        totp_secret: Option<String>,
+        /// Use a running browser through its DevTools websocket url, instead of launching one
+        #[arg(long, env = "PINTEREST_BROWSER_URL")]
+        browser_url: Option<String>,
+        /// A persistent browser profile directory, reusing it makes the logins trigger fewer challenges
+        #[arg(long)]
+        profile: Option<PathBuf>,
+        /// Disable the browser sandbox, needed to run as root
+        #[arg(long)]
+        no_sandbox: bool,
+        /// Show the browser window
+        #[arg(long)]
+        headed: bool,
+        /// The browser request timeout in seconds
+        #[arg(long, default_value_t = 8)]
+        timeout: u64,
+        /// Save a screenshot, the page html, the url and the cookies to this directory when the login fails
+        #[arg(long)]
+        diagnostics_dir: Option<PathBuf>,
+        /// Hide the signs of an automated headless browser
+        #[arg(long)]
+        stealth: bool,
+        /// Type and click like a human, with delays and mouse movements
+        #[arg(long)]
+        human: bool,
+    },
+    /// Shows the logged in account
+    Whoami,
+    /// Lists the boards
+    Boards {
+        #[command(subcommand)]
+        command: BoardsCommand,
+    },
+    /// Lists the pins of a board
+    Pins {
+        #[command(subcommand)]
+        command: PinsCommand,
+    },
+    /// Gets a single pin
+    Pin {
+        #[command(subcommand)]
+        command: PinCommand,
+    },
+    /// Searches the pins
+    Search {
+        /// The search query
+        query: String,
+        /// The maximum number of pins to return
+        #[arg(short, long, default_value_t = 25)]
+        limit: usize,
+    },
+    /// Downloads an image from its pinimg url
+    Download {
+        /// The pinimg url of the image, at any size
+        url: String,
+        /// The preferred size, smaller sizes are tried when it's not available
+        #[arg(short, long, value_enum, default_value_t = SizeArg::Orig)]
+        size: SizeArg,
+        /// The file to write to, the extension is derived from the content type when missing
+        #[arg(short = 'f', long)]
+        file: Option<PathBuf>,
+    },
+    /// Mirrors a board into a directory, the next runs only download the new pins
+    Mirror {
+        /// The board path like `username/board-name`, or its url
+        board: String,
+        /// The mirror directory
+        dir: PathBuf,
+        /// How many pins are downloaded at the same time
+        #[arg(short, long, default_value_t = 4)]
+        concurrency: usize,
+        /// The preferred image size, smaller sizes are tried when it's not available
+        #[arg(short, long, value_enum, default_value_t = SizeArg::Orig)]
+        size: SizeArg,
+        /// Only download the cover image of the video pins
+        #[arg(long)]
+        no_videos: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum BoardsCommand {
+    /// Lists the boards of a user
+    List {
+        /// The username of the boards owner, the logged in user by default
+        username: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PinsCommand {
+    /// Lists the pins of a board, including the pins of its sections
+    List {
+        /// The board path like `username/board-name`, or its url
+        board: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum PinCommand {
+    /// Gets a pin from its id
+    Get {
+        /// The pin id
+        id: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SizeArg {
+    Orig,
+    #[value(name = "736x")]
+    W736,
+    #[value(name = "474x")]
+    W474,
+    #[value(name = "236x")]
+    W236,
+}
+
+impl From<SizeArg> for ImageSize {
+    fn from(size: SizeArg) -> Self {
+        match size {
+            SizeArg::Orig => ImageSize::Original,
+            SizeArg::W736 => ImageSize::W736,
+            SizeArg::W474 => ImageSize::W474,
+            SizeArg::W236 => ImageSize::W236,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match async_std::task::block_on(run(cli)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn run(cli: Cli) -> pinterest_rs::Result<()> {
+    match cli.command {
+        Command::Login { email, password, totp_secret, browser_url, profile, no_sandbox, headed, timeout, diagnostics_dir, stealth, human } => {
+            let mut login_options = LoginOptions::new();
+            if let Some(dir) = diagnostics_dir {
+                login_options = login_options.with_diagnostics_dir(dir);
+            }
+            if stealth {
+                login_options = login_options.with_stealth(StealthOptions::default());
+            }
+            let mut pinterest = new_client(&email, cli.user_agent.as_deref()).with_login_options(login_options);
+            let mut bot = DefaultBrowserLoginBot::new(&email, &password);
+            if human {
+                bot = bot.with_interaction(InteractionOptions::default());
+            }
+            let bot = match totp_secret {
+                Some(secret) => bot.with_two_factor(TotpCodeProvider::new(&secret)?),
+                None => bot.with_two_factor(StdinCodeProvider::new()),
+            };
+            let cookies = cli.cookies;
+            match browser_url {
+                Some(browser_url) => pinterest.login_with_remote_browser(&bot, &browser_url, Some(&cookies)).await?,
+                None => {
+                    let mut config = DefaultBrowserConfigBuilder::new(!headed, Some(Duration::from_secs(timeout)), None)
+                        .with_sandbox(!no_sandbox);
+                    if let Some(profile) = profile {
+                        config = config.with_user_data_dir(profile);
+                    }
+                    pinterest.login_with_custom_bot_and_config(&bot, &config, Some(&cookies)).await?
+                }
+            }
+            // The client was created with the email, ask pinterest for the account username
+            let username = pinterest.whoami().await.ok().map(|user| user.username);
+            print_rows(cli.output, &[
+                ("username", json!(username)),
+                ("email", json!(email)),
+                ("cookies", json!(cookies)),
+            ]);
+        }
+        Command::Whoami => {
+            let user = session_client(&cli.cookies, cli.user_agent.as_deref(), true)?.whoami().await?;
+            print_rows(cli.output, &[
+                ("username", json!(user.username)),
+                ("id", json!(user.id)),
+                ("full_name", json!(user.full_name)),
+                ("email", json!(user.email)),
+                ("followers", json!(user.follower_count)),
+                ("boards", json!(user.board_count)),
+                ("pins", json!(user.pin_count)),
+            ]);
+        }
+        Command::Boards { command: BoardsCommand::List { username } } => {
+            let pinterest = session_client(&cli.cookies, cli.user_agent.as_deref(), username.is_none())?;
+            let username = match username {
+                Some(username) => username,
+                None => pinterest.whoami().await?.username,
+            };
+            let boards = pinterest.user_boards(&username).await?;
+            print_list(cli.output, &["id", "name", "url", "pins", "sections", "privacy"], boards.iter()
+                .map(|board| vec![
+                    json!(board.id), json!(board.name), json!(board.url),
+                    json!(board.pin_count), json!(board.section_count), json!(board.privacy),
+                ])
+                .collect());
+        }
+        Command::Pins { command: PinsCommand::List { board } } => {
+            let pinterest = session_client(&cli.cookies, cli.user_agent.as_deref(), false)?;
+            let board = pinterest.board(&board).await?;
+            let mut pins: Vec<(Option<String>, Pin)> = pinterest.board_pins(&board).await?
+                .into_iter()
+                .map(|pin| (None, pin))
+                .collect();
+            for section in pinterest.board_sections(&board).await? {
+                let section_pins = pinterest.section_pins(&board, &section).await?;
+                pins.extend(section_pins.into_iter().map(|pin| (Some(section.title.clone()), pin)));
+            }
+            print_list(cli.output, &["id", "section", "title", "image"], pins.iter()
+                .map(|(section, pin)| vec![json!(pin.id), json!(section), json!(pin.title), json!(pin.image_url())])
+                .collect());
+        }
+        Command::Pin { command: PinCommand::Get { id } } => {
+            let pin = session_client(&cli.cookies, cli.user_agent.as_deref(), false)?.pin(&id).await?;
+            print_rows(cli.output, &[
+                ("id", json!(pin.id)),
+                ("title", json!(pin.title)),
+                ("description", json!(pin.description)),
+                ("link", json!(pin.link)),
+                ("created_at", json!(pin.created_at)),
+                ("image", json!(pin.image_url())),
+                ("video", json!(pin.video_list().is_some())),
+            ]);
+        }
+        Command::Search { query, limit } => {
+            let pins = session_client(&cli.cookies, cli.user_agent.as_deref(), false)?.search(&query, limit).await?;
+            print_list(cli.output, &["id", "title", "image"], pins.iter()
+                .map(|pin| vec![json!(pin.id), json!(pin.title), json!(pin.image_url())])
+                .collect());
+        }
+        Command::Download { url, size, file } => {
+            let pinterest = new_client("", cli.user_agent.as_deref());
+            let file = file.unwrap_or_else(|| file_name_from_url(&url));
+            let download = pinterest.download_image(&url, size.into(), file).await?;
+            print_rows(cli.output, &[
+                ("url", json!(download.url.as_str())),
+                ("size", json!(download.size.path_segment())),
+                ("content_type", json!(download.content_type)),
+                ("path", json!(download.path)),
+                ("bytes", json!(download.len)),
+                ("skipped", json!(download.skipped)),
+            ]);
+        }
+        Command::Mirror { board, dir, concurrency, size, no_videos } => {
+            let pinterest = session_client(&cli.cookies, cli.user_agent.as_deref(), false)?;
+            let options = MirrorOptions::new()
+                .with_concurrency(concurrency)
+                .with_image_size(size.into())
+                .with_videos(!no_videos);
+            let report = pinterest.mirror_board(&board, &dir, &options).await?;
+            for (id, error) in &report.failed {
+                eprintln!("warning: couldn't download the pin {id}: {error}");
+            }
+            print_rows(cli.output, &[
+                ("manifest", json!(report.manifest_path)),
+                ("pins", json!(report.pins)),
+                ("downloaded", json!(report.downloaded)),
+                ("unchanged", json!(report.unchanged)),
+                ("removed", json!(report.removed)),
+                ("failed", json!(report.failed.iter().map(|(id, _)| id).collect::<Vec<_>>())),
+            ]);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "credentials"))]
+fn new_client(username: &str, user_agent: Option<&str>) -> Pinterest {
+    Pinterest::new(username, user_agent)
+}
+
+#[cfg(feature = "credentials")]
+fn new_client(username: &str, user_agent: Option<&str>) -> Pinterest {
+    // The crf token is set from the cookies after logging in
+    Pinterest::new(username, "", user_agent)
+}
+
+/// Creates a client with the session of the cookies file,
+/// an anonymous client is returned when the session is optional and the file doesn't exist
+fn session_client(cookies: &Path, user_agent: Option<&str>, required: bool) -> pinterest_rs::Result<Pinterest> {
+    let mut pinterest = new_client("", user_agent);
+    if required || cookies.exists() {
+        pinterest.load_credentials(cookies)?;
+    }
+    Ok(pinterest)
+}
+
+/// Uses the last url path segment without its extension, so the extension comes from the content type
+fn file_name_from_url(url: &str) -> PathBuf {
+    let name = url.split(['?', '#']).next().unwrap_or_default()
+        .rsplit('/').next().unwrap_or_default();
+    let stem = name.split('.').next().filter(|stem| !stem.is_empty()).unwrap_or("image");
+    PathBuf::from(stem)
+}
+
+fn print_rows(format: OutputFormat, rows: &[(&str, serde_json::Value)]) {
+    match format {
+        OutputFormat::Json => {
+            let object: serde_json::Map<String, serde_json::Value> = rows.iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect();
+            println!("{}", serde_json::Value::Object(object));
+        }
+        OutputFormat::Table => {
+            let width = rows.iter().map(|(key, _)| key.len()).max().unwrap_or_default();
+            for (key, value) in rows {
+                println!("{key:<width$}  {}", cell(value));
+            }
+        }
+    }
+}
+
+/// Prints a list as a json array of objects, or as a table with a header line
+fn print_list(format: OutputFormat, columns: &[&str], rows: Vec<Vec<serde_json::Value>>) {
+    match format {
+        OutputFormat::Json => {
+            let list: Vec<serde_json::Value> = rows.into_iter()
+                .map(|row| serde_json::Value::Object(columns.iter().map(|column| column.to_string()).zip(row).collect()))
+                .collect();
+            println!("{}", serde_json::Value::Array(list));
+        }
+        OutputFormat::Table => {
+            let cells: Vec<Vec<String>> = rows.iter().map(|row| row.iter().map(cell).collect()).collect();
+            let widths: Vec<usize> = columns.iter().enumerate()
+                .map(|(i, column)| cells.iter().map(|row| row[i].chars().count()).max().unwrap_or_default().max(column.len()))
+                .collect();
+            let header: Vec<String> = columns.iter().map(|column| column.to_uppercase()).collect();
+            for row in std::iter::once(&header).chain(&cells) {
+                let line: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{cell:<width$}")).collect();
+                println!("{}", line.join("  ").trim_end());
+            }
+        }
+    }
+}
+
+#[inline]
+fn cell(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => "-".to_string(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{Pinterest, PinterestError, PINTEREST_BASE_URL};
+use crate::pin::Pin;
+
+/// The number of items requested per page of the list resources
+pub(crate) const PAGE_SIZE: u32 = 25;
+
+/// A pinterest board
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Board {
+    /// The board id
+    pub id: String,
+    /// The board name
+    #[serde(default)]
+    pub name: String,
+    /// The board path, like `/username/board-name/`
+    #[serde(default)]
+    pub url: String,
+    /// The board description, if any
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The number of pins of the board, including the pins of its sections
+    #[serde(default)]
+    pub pin_count: u64,
+    /// The number of sections of the board
+    #[serde(default)]
+    pub section_count: u64,
+    /// The board privacy, like `public` or `secret`
+    #[serde(default)]
+    pub privacy: Option<String>,
+}
+
+/// A section of a pinterest board
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardSection {
+    /// The section id
+    pub id: String,
+    /// The section title
+    #[serde(default)]
+    pub title: String,
+    /// The section slug, used in its url
+    #[serde(default)]
+    pub slug: String,
+    /// The number of pins of the section
+    #[serde(default)]
+    pub pin_count: u64,
+}
+
+impl Pinterest {
+    /// Gets a board from its path or its url
+    ///
+    /// # Arguments
+    /// * `board` - The board path like `username/board-name`, or its url like `https://www.pinterest.com/username/board-name/`
+    ///
+    /// # Errors
+    /// * `InvalidBoard` - If the board is not a `username/board-name` path or url
+    /// * `NotFound` - If the board doesn't exist or is not visible to this account
+    pub async fn board(&self, board: &str) -> crate::Result<Board> {
+        let (username, slug) = parse_board_path(board)?;
+        self.get_resource_data("BoardResource", &format!("/{username}/{slug}/"), json!({
+            "username": username,
+            "slug": slug,
+            "field_set_key": "detailed",
+        })).await
+    }
+
+    /// Gets all the boards of a user, including the secret ones when it's the logged in user
+    ///
+    /// # Arguments
+    /// * `username` - The username of the boards owner
+    pub async fn user_boards(&self, username: &str) -> crate::Result<Vec<Board>> {
+        self.get_resource_list("BoardsResource", &format!("/{username}/boards/"), json!({
+            "username": username,
+            "page_size": PAGE_SIZE,
+            "privacy_filter": "all",
+            "sort": "last_pinned_to",
+            "field_set_key": "profile_grid_item",
+        }), None, |item| item["type"].as_str().is_none_or(|item_type| item_type == "board")).await
+    }
+
+    /// Gets all the sections of a board
+    pub async fn board_sections(&self, board: &Board) -> crate::Result<Vec<BoardSection>> {
+        self.get_resource_list("BoardSectionsResource", &board.url, json!({
+            "board_id": board.id,
+            "page_size": PAGE_SIZE,
+        }), None, |_| true).await
+    }
+
+    /// Gets all the pins of a board that are not in one of its sections
+    pub async fn board_pins(&self, board: &Board) -> crate::Result<Vec<Pin>> {
+        self.get_resource_list("BoardFeedResource", &board.url, json!({
+            "board_id": board.id,
+            "board_url": board.url,
+            "page_size": PAGE_SIZE,
+            "filter_section_pins": true,
+            "field_set_key": "react_grid_pin",
+        }), None, is_pin).await
+    }
+
+    /// Gets all the pins of a board section
+    pub async fn section_pins(&self, board: &Board, section: &BoardSection) -> crate::Result<Vec<Pin>> {
+        self.get_resource_list("BoardSectionPinsResource", &format!("{}{}/", board.url, section.slug), json!({
+            "section_id": section.id,
+            "page_size": PAGE_SIZE,
+        }), None, is_pin).await
+    }
+}
+
+/// The feeds mix the pins with other items, like stories, that have another `type`
+#[inline(always)]
+pub(crate) fn is_pin(item: &Value) -> bool {
+    item["type"].as_str().is_none_or(|item_type| item_type == "pin")
+}
+
+/// Returns the username and the board slug of a board path or url
+fn parse_board_path(board: &str) -> crate::Result<(&str, &str)> {
+    let path = board.split(['?', '#']).next().unwrap_or_default();
+    let path = path.strip_prefix(PINTEREST_BASE_URL)
+        .or_else(|| path.split_once("://").and_then(|(_, rest)| rest.split_once('/')).map(|(_, path)| path))
+        .unwrap_or(path);
+
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+    match (segments.next(), segments.next(), segments.next()) {
+        (Some(username), Some(slug), None) => Ok((username, slug)),
+        _ => Err(PinterestError::InvalidBoard(board.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_board_path_accepts_paths_and_urls() {
+        assert_eq!(parse_board_path("username/my-board").unwrap(), ("username", "my-board"));
+        assert_eq!(parse_board_path("/username/my-board/").unwrap(), ("username", "my-board"));
+        assert_eq!(parse_board_path("https://www.pinterest.com/username/my-board/").unwrap(), ("username", "my-board"));
+        assert_eq!(parse_board_path("https://pinterest.fr/username/my-board/?invite=1").unwrap(), ("username", "my-board"));
+    }
+
+    #[test]
+    fn parse_board_path_rejects_other_paths() {
+        assert!(matches!(parse_board_path("username"), Err(PinterestError::InvalidBoard(_))));
+        assert!(matches!(parse_board_path("username/my-board/section"), Err(PinterestError::InvalidBoard(_))));
+        assert!(matches!(parse_board_path(""), Err(PinterestError::InvalidBoard(_))));
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+use futures::future::BoxFuture;
+
+/// The clock used by the rate limiter and the retry policy to read the time and to wait
+///
+/// The default [`SystemClock`] uses the enabled runtime to sleep,
+/// you can implement this trait with a mock clock to test the limits without waiting
+///
+/// # Example
+/// ```
+/// use std::sync::Mutex;
+/// use std::time::{Duration, Instant};
+/// use futures::future::BoxFuture;
+/// use pinterest_rs::clock::Clock;
+///
+/// struct MockClock(Mutex<Instant>);
+///
+/// impl Clock for MockClock {
+///     fn now(&self) -> Instant {
+///         *self.0.lock().unwrap()
+///     }
+///
+///     fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+///         // Advance the time instead of waiting
+///         *self.0.lock().unwrap() += duration;
+///         Box::pin(async {})
+///     }
+/// }
+/// ```
+pub trait Clock: Send + Sync {
+    /// Returns the current instant
+    fn now(&self) -> Instant;
+    /// Waits for the given duration
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The real clock, sleeps with the tokio timer when called from a tokio runtime, then with the async-std timer,
+/// or with a helper thread when no runtime feature is enabled
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    #[inline]
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    #[inline]
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        #[cfg(feature = "tokio-runtime")]
+        if tokio::runtime::Handle::try_current().is_ok() {
+            return Box::pin(tokio::time::sleep(duration));
+        }
+
+        #[cfg(feature = "async-std-runtime")]
+        return Box::pin(async_std::task::sleep(duration));
+
+        #[cfg(not(feature = "async-std-runtime"))]
+        {
+            let (sender, receiver) = futures::channel::oneshot::channel::<()>();
+            std::thread::spawn(move || {
+                std::thread::sleep(duration);
+                let _ = sender.send(());
+            });
+            Box::pin(async move {
+                let _ = receiver.await;
+            })
+        }
+    }
+}
+
+/// A clock that advances its time instead of waiting, and records the sleeps
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub(crate) struct MockClock {
+    now: std::sync::Arc<std::sync::Mutex<Instant>>,
+    sleeps: std::sync::Arc<std::sync::Mutex<Vec<Duration>>>,
+}
+
+#[cfg(test)]
+impl MockClock {
+    pub(crate) fn new() -> Self {
+        Self {
+            now: std::sync::Arc::new(std::sync::Mutex::new(Instant::now())),
+            sleeps: Default::default(),
+        }
+    }
+
+    pub(crate) fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+
+    pub(crate) fn sleeps(&self) -> Vec<Duration> {
+        self.sleeps.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        self.advance(duration);
+        self.sleeps.lock().unwrap().push(duration);
+        Box::pin(async {})
+    }
+}
@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use pinterest_login::cookie::SessionCookie;
+use serde::Deserialize;
+#[cfg(feature = "debug")]
+use log::*;
+
+use crate::PinterestError;
+
+/// The session cookies, with all their attributes
+pub type Session = Vec<SessionCookie>;
+
+/// The credentials files can be a list of cookies, or a map of cookie names to values (the format of the older versions)
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SessionFile {
+    Cookies(Session),
+    Legacy(HashMap<String, String>),
+}
+
+/// Deserializes a session from a credentials file, in the current or the legacy format
+pub(crate) fn parse_session(session_json: &[u8]) -> crate::Result<Session> {
+    match serde_json::from_slice(session_json).map_err(|_| PinterestError::InvalidCredentialsFile)? {
+        SessionFile::Cookies(session) => Ok(session),
+        SessionFile::Legacy(cookies) => Ok(cookies.into_iter()
+            .map(|(name, value)| SessionCookie::new(name, value))
+            .collect()),
+    }
+}
+
+/// Writes a credentials file, only readable and writable by its owner on unix
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut file = options.open(path)?;
+    // The mode only applies to the new files, the permissions of an existing file are restricted before writing to it
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    file.write_all(contents)
+}
+
+/// The credential store trait, that provides methods to load, save and delete the session of an account
+/// You can implement this trait for your own struct, to keep the sessions in a database or a secret manager
+///
+/// The file paths (`&str`, `String`, `&Path` and `PathBuf`) implement this trait as a [`FileCredentialStore`],
+/// so they can be passed to the login methods directly
+///
+/// # Example
+/// ```
+/// use pinterest_rs::credentials::{CredentialStore, Session};
+///
+/// struct MyCredentialStore;
+///
+/// impl CredentialStore for MyCredentialStore {
+///     fn load(&self) -> pinterest_rs::Result<Option<Session>> {
+///         // Read the session from wherever you want
+///         Ok(None)
+///     }
+///
+///     fn save(&self, session: &Session) -> pinterest_rs::Result<()> {
+///         // ...
+///         Ok(())
+///     }
+///
+///     fn delete(&self) -> pinterest_rs::Result<()> {
+///         // ...
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait CredentialStore {
+    /// Loads the saved session, returns `None` if there is no saved session
+    fn load(&self) -> crate::Result<Option<Session>>;
+    /// Saves the session, replacing the previous one
+    fn save(&self, session: &Session) -> crate::Result<()>;
+    /// Deletes the saved session, does nothing if there is no saved session
+    fn delete(&self) -> crate::Result<()>;
+}
+
+impl<T: CredentialStore + ?Sized> CredentialStore for &T {
+    #[inline]
+    fn load(&self) -> crate::Result<Option<Session>> {
+        (**self).load()
+    }
+
+    #[inline]
+    fn save(&self, session: &Session) -> crate::Result<()> {
+        (**self).save(session)
+    }
+
+    #[inline]
+    fn delete(&self) -> crate::Result<()> {
+        (**self).delete()
+    }
+}
+
+macro_rules! impl_credential_store_for_path {
+    ($($path:ty),*) => {
+        $(
+            impl CredentialStore for $path {
+                #[inline]
+                fn load(&self) -> crate::Result<Option<Session>> {
+                    FileCredentialStore::new(self).load()
+                }
+
+                #[inline]
+                fn save(&self, session: &Session) -> crate::Result<()> {
+                    FileCredentialStore::new(self).save(session)
+                }
+
+                #[inline]
+                fn delete(&self) -> crate::Result<()> {
+                    FileCredentialStore::new(self).delete()
+                }
+            }
+        )*
+    };
+}
+
+impl_credential_store_for_path!(str, String, Path, PathBuf);
+
+/// Stores the session as a plain json list of cookies, readable by anyone who can read the file,
+/// the file is created with the `0600` permissions on unix
+#[derive(Debug, Clone)]
+pub struct FileCredentialStore {
+    path: PathBuf,
+}
+
+impl FileCredentialStore {
+    /// Creates a new file credential store
+    ///
+    /// # Arguments
+    /// * `path` - The path of the credentials file
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Returns the path of the credentials file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl CredentialStore for FileCredentialStore {
+    fn load(&self) -> crate::Result<Option<Session>> {
+        #[cfg(feature = "debug")]
+        info!("Loading the session from the credentials file: {:?}", self.path);
+
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        parse_session(&std::fs::read(&self.path)?).map(Some)
+    }
+
+    fn save(&self, session: &Session) -> crate::Result<()> {
+        #[cfg(feature = "debug")]
+        info!("Saving the session to the credentials file: {:?}", self.path);
+
+        write_private(&self.path, serde_json::to_string(session)?.as_bytes())?;
+        Ok(())
+    }
+
+    fn delete(&self) -> crate::Result<()> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Keeps the session in memory, useful for tests
+#[derive(Debug, Default)]
+pub struct MemoryCredentialStore {
+    session: Mutex<Option<Session>>,
+}
+
+impl MemoryCredentialStore {
+    /// Creates a new empty memory credential store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new memory credential store with a saved session
+    pub fn with_session(session: Session) -> Self {
+        Self {
+            session: Mutex::new(Some(session)),
+        }
+    }
+}
+
+impl CredentialStore for MemoryCredentialStore {
+    fn load(&self) -> crate::Result<Option<Session>> {
+        Ok(self.session.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    fn save(&self, session: &Session) -> crate::Result<()> {
+        *self.session.lock().unwrap_or_else(|e| e.into_inner()) = Some(session.clone());
+        Ok(())
+    }
+
+    fn delete(&self) -> crate::Result<()> {
+        *self.session.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "encryption")]
+pub use encrypted::EncryptedFileCredentialStore;
+
+#[cfg(feature = "encryption")]
+mod encrypted {
+    use std::path::Path;
+    use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use chacha20poly1305::aead::rand_core::RngCore;
+    use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+    use zeroize::Zeroizing;
+    #[cfg(feature = "debug")]
+    use log::*;
+
+    use crate::PinterestError;
+    use super::{write_private, CredentialStore, FileCredentialStore, Session};
+
+    /// The header of the encrypted credentials files, followed by the format version
+    const MAGIC: &[u8; 4] = b"PRSC";
+    const VERSION: u8 = 1;
+    const SALT_LEN: usize = 16;
+    const NONCE_LEN: usize = 12;
+
+    /// Stores the session in a file encrypted with ChaCha20-Poly1305,
+    /// with a key derived from a passphrase with Argon2id (and a random salt per file)
+    ///
+    /// The passphrase, the key and the decrypted session are zeroed in memory once dropped,
+    /// the file is created with the `0600` permissions on unix
+    ///
+    /// # Example
+    /// ```no_run
+    /// use pinterest_rs::credentials::{CredentialStore, EncryptedFileCredentialStore};
+    ///
+    /// let store = EncryptedFileCredentialStore::new("session.bin", std::env::var("SESSION_PASSPHRASE").unwrap());
+    /// let session = store.load()?;
+    /// # Ok::<(), pinterest_rs::PinterestError>(())
+    /// ```
+    pub struct EncryptedFileCredentialStore {
+        file: FileCredentialStore,
+        passphrase: Zeroizing<String>,
+    }
+
+    impl EncryptedFileCredentialStore {
+        /// Creates a new encrypted file credential store
+        ///
+        /// # Arguments
+        /// * `path` - The path of the encrypted credentials file
+        /// * `passphrase` - The passphrase to derive the encryption key from
+        pub fn new<P: AsRef<Path>, S: Into<String>>(path: P, passphrase: S) -> Self {
+            Self {
+                file: FileCredentialStore::new(path),
+                passphrase: Zeroizing::new(passphrase.into()),
+            }
+        }
+
+        /// Returns the path of the encrypted credentials file
+        pub fn path(&self) -> &Path {
+            self.file.path()
+        }
+
+        fn cipher(&self, salt: &[u8]) -> crate::Result<ChaCha20Poly1305> {
+            let mut key = Zeroizing::new([0u8; 32]);
+            argon2::Argon2::default()
+                .hash_password_into(self.passphrase.as_bytes(), salt, key.as_mut())
+                .map_err(|e| PinterestError::KeyDerivationError(e.to_string()))?;
+            Ok(ChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+        }
+    }
+
+    impl std::fmt::Debug for EncryptedFileCredentialStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("EncryptedFileCredentialStore")
+                .field("path", &self.file.path())
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl CredentialStore for EncryptedFileCredentialStore {
+        fn load(&self) -> crate::Result<Option<Session>> {
+            #[cfg(feature = "debug")]
+            info!("Loading the session from the encrypted credentials file: {:?}", self.path());
+
+            let data = match std::fs::read(self.path()) {
+                Ok(data) => data,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+
+            let header_len = MAGIC.len() + 1;
+            if data.len() < header_len + SALT_LEN + NONCE_LEN || &data[..MAGIC.len()] != MAGIC || data[MAGIC.len()] != VERSION {
+                return Err(PinterestError::InvalidCredentialsFile);
+            }
+            let (salt, rest) = data[header_len..].split_at(SALT_LEN);
+            let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+            let session_json = Zeroizing::new(self.cipher(salt)?
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .map_err(|_| PinterestError::CredentialsDecryptionError)?);
+
+            super::parse_session(&session_json).map(Some)
+        }
+
+        fn save(&self, session: &Session) -> crate::Result<()> {
+            #[cfg(feature = "debug")]
+            info!("Saving the session to the encrypted credentials file: {:?}", self.path());
+
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+            let ciphertext = self.cipher(&salt)?
+                .encrypt(&nonce, Zeroizing::new(serde_json::to_vec(session)?).as_slice())
+                .map_err(|_| PinterestError::CredentialsEncryptionError)?;
+
+            let mut data = Vec::with_capacity(MAGIC.len() + 1 + SALT_LEN + NONCE_LEN + ciphertext.len());
+            data.extend_from_slice(MAGIC);
+            data.push(VERSION);
+            data.extend_from_slice(&salt);
+            data.extend_from_slice(&nonce);
+            data.extend_from_slice(&ciphertext);
+
+            write_private(self.path(), &data)?;
+            Ok(())
+        }
+
+        #[inline]
+        fn delete(&self) -> crate::Result<()> {
+            self.file.delete()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::path::PathBuf;
+        use pinterest_login::cookie::SessionCookie;
+
+        use super::*;
+
+        fn path(name: &str) -> PathBuf {
+            std::env::temp_dir().join(format!("pinterest_rs_{name}_{}.bin", std::process::id()))
+        }
+
+        fn session() -> Session {
+            vec![SessionCookie::new("csrftoken", "1234"), SessionCookie::new("_pinterest_sess", "secret")]
+        }
+
+        #[test]
+        fn the_session_round_trips_and_is_encrypted() {
+            let path = path("round_trip");
+            let store = EncryptedFileCredentialStore::new(&path, "passphrase");
+
+            store.save(&session()).unwrap();
+            let data = std::fs::read(&path).unwrap();
+            assert!(data.starts_with(MAGIC));
+            assert!(!data.windows(6).any(|window| window == b"secret"));
+            assert_eq!(store.load().unwrap(), Some(session()));
+
+            #[cfg(unix)] {
+                use std::os::unix::fs::PermissionsExt;
+                assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+            }
+
+            store.delete().unwrap();
+            assert_eq!(store.load().unwrap(), None);
+        }
+
+        #[test]
+        fn a_wrong_passphrase_is_rejected() {
+            let path = path("wrong_passphrase");
+            EncryptedFileCredentialStore::new(&path, "passphrase").save(&session()).unwrap();
+
+            let result = EncryptedFileCredentialStore::new(&path, "another passphrase").load();
+            std::fs::remove_file(&path).unwrap();
+            assert!(matches!(result, Err(PinterestError::CredentialsDecryptionError)));
+        }
+
+        #[test]
+        fn a_file_of_another_format_is_rejected() {
+            let path = path("other_format");
+            std::fs::write(&path, b"[{\"name\": \"csrftoken\"}]").unwrap();
+
+            let result = EncryptedFileCredentialStore::new(&path, "passphrase").load();
+            std::fs::remove_file(&path).unwrap();
+            assert!(matches!(result, Err(PinterestError::InvalidCredentialsFile)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pinterest_rs_{name}_{}.json", std::process::id()))
+    }
+
+    fn session() -> Session {
+        vec![SessionCookie::new("csrftoken", "1234"), SessionCookie::new("_pinterest_sess", "secret")]
+    }
+
+    #[test]
+    fn the_file_store_round_trips() {
+        let store = FileCredentialStore::new(path("file_round_trip"));
+        assert_eq!(store.load().unwrap(), None);
+
+        store.save(&session()).unwrap();
+        assert_eq!(store.load().unwrap(), Some(session()));
+        // The paths are file stores too
+        assert_eq!(store.path().load().unwrap(), Some(session()));
+
+        store.delete().unwrap();
+        assert_eq!(store.load().unwrap(), None);
+        store.delete().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_file_store_is_only_readable_by_its_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let store = FileCredentialStore::new(path("file_permissions"));
+        // An existing file keeps its permissions when it's opened, they are restricted anyway
+        std::fs::write(store.path(), b"[]").unwrap();
+        std::fs::set_permissions(store.path(), std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        store.save(&session()).unwrap();
+        let mode = std::fs::metadata(store.path()).unwrap().permissions().mode();
+        store.delete().unwrap();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn the_file_store_reads_the_legacy_format() {
+        let store = FileCredentialStore::new(path("file_legacy"));
+        std::fs::write(store.path(), br#"{"csrftoken": "1234"}"#).unwrap();
+        let loaded = store.load();
+        std::fs::write(store.path(), b"not json").unwrap();
+        let invalid = store.load();
+        store.delete().unwrap();
+
+        assert_eq!(loaded.unwrap(), Some(vec![SessionCookie::new("csrftoken", "1234")]));
+        assert!(matches!(invalid, Err(PinterestError::InvalidCredentialsFile)));
+    }
+
+    #[test]
+    fn the_memory_store_round_trips() {
+        let store = MemoryCredentialStore::new();
+        assert_eq!(store.load().unwrap(), None);
+
+        store.save(&session()).unwrap();
+        assert_eq!(store.load().unwrap(), Some(session()));
+        store.delete().unwrap();
+        assert_eq!(store.load().unwrap(), None);
+
+        let store = MemoryCredentialStore::with_session(session());
+        assert_eq!(store.load().unwrap(), Some(session()));
+    }
+}
@@ -0,0 +1,390 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use futures::{AsyncWrite, AsyncWriteExt};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE, ETAG};
+use reqwest::Response;
+use url::Url;
+#[cfg(feature = "debug")]
+use log::*;
+
+use crate::{Pinterest, PinterestError};
+
+/// The image sizes served by the pinterest image cdn (`i.pinimg.com`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ImageSize {
+    /// The original uploaded image (`originals`)
+    #[default]
+    Original,
+    /// 736 pixels wide (`736x`)
+    W736,
+    /// 474 pixels wide (`474x`)
+    W474,
+    /// 236 pixels wide (`236x`)
+    W236,
+}
+
+impl ImageSize {
+    /// All the sizes, from the largest to the smallest
+    pub const ALL: [ImageSize; 4] = [ImageSize::Original, ImageSize::W736, ImageSize::W474, ImageSize::W236];
+
+    /// Returns the url path segment of the size, like `originals` or `736x`
+    pub fn path_segment(&self) -> &'static str {
+        match self {
+            ImageSize::Original => "originals",
+            ImageSize::W736 => "736x",
+            ImageSize::W474 => "474x",
+            ImageSize::W236 => "236x",
+        }
+    }
+
+    /// Returns the size for a url path segment, like `originals` or `736x`
+    pub fn from_path_segment(segment: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|size| size.path_segment() == segment)
+    }
+
+    /// Returns this size followed by the smaller ones, in the order they are tried when downloading
+    pub fn fallbacks(&self) -> impl Iterator<Item = ImageSize> {
+        let this = *self;
+        Self::ALL.into_iter().filter(move |size| *size >= this)
+    }
+
+    /// Rewrites a pinimg url to point to this size
+    ///
+    /// # Example
+    /// ```
+    /// use pinterest_rs::download::ImageSize;
+    ///
+    /// let url = ImageSize::W236.image_url("https://i.pinimg.com/originals/ab/cd/ef/abcdef.jpg")?;
+    /// assert_eq!(url.as_str(), "https://i.pinimg.com/236x/ab/cd/ef/abcdef.jpg");
+    /// # Ok::<(), pinterest_rs::PinterestError>(())
+    /// ```
+    ///
+    /// # Errors
+    /// * `InvalidImageUrl` - If the url is not a pinimg url with a size segment
+    pub fn image_url(&self, url: &str) -> crate::Result<Url> {
+        let invalid = || PinterestError::InvalidImageUrl(url.to_string());
+
+        let mut url = Url::parse(url).map_err(|_| invalid())?;
+        let mut segments: Vec<String> = url.path_segments()
+            .ok_or_else(invalid)?
+            .map(str::to_string)
+            .collect();
+
+        match segments.first() {
+            Some(first) if ImageSize::from_path_segment(first).is_some() || is_size_segment(first) => {
+                segments[0] = self.path_segment().to_string();
+            }
+            _ => return Err(invalid()),
+        }
+
+        url.set_path(&segments.join("/"));
+        Ok(url)
+    }
+}
+
+/// Pinterest serves more sizes than the ones in [`ImageSize`], like `564x` or `170x`
+#[inline(always)]
+fn is_size_segment(segment: &str) -> bool {
+    segment.strip_suffix('x')
+        .is_some_and(|width| !width.is_empty() && width.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Returns the file extension for a content type, like `jpg` for `image/jpeg`
+pub fn extension_for_content_type(content_type: &str) -> Option<&'static str> {
+    let mime = content_type.split(';').next()?.trim().to_ascii_lowercase();
+    Some(match mime.as_str() {
+        "image/jpeg" | "image/jpg" | "image/pjpeg" => "jpg",
+        "image/png" => "png",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/avif" => "avif",
+        "image/heic" => "heic",
+        "image/bmp" => "bmp",
+        "image/svg+xml" => "svg",
+        "video/mp4" => "mp4",
+        "video/mp2t" => "ts",
+        "application/vnd.apple.mpegurl" | "application/x-mpegurl" => "m3u8",
+        _ => return None,
+    })
+}
+
+/// The result of a successful download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Download {
+    /// The size that was actually downloaded, after the fallbacks
+    pub size: ImageSize,
+    /// The url that was actually downloaded
+    pub url: Url,
+    /// The content type returned by the server, if any
+    pub content_type: Option<String>,
+    /// The file the image was written to, `None` when writing to an [`AsyncWrite`]
+    pub path: Option<PathBuf>,
+    /// The length of the image in bytes
+    pub len: u64,
+    /// True if the file was already on disk with the same size and hash, and wasn't written again
+    pub skipped: bool,
+}
+
+impl Pinterest {
+    /// Downloads a pinterest image at the given size into a file,
+    /// falling back to the next smaller size when the server answers with 403 or 404
+    ///
+    /// If the path has no extension, it's derived from the content type of the response.
+    /// If the file already exists with the same size and md5 hash as the remote image, it's not written again.
+    ///
+    /// # Arguments
+    /// * `image_url` - The pinimg url of the image, at any size
+    /// * `size` - The preferred size to download
+    /// * `path` - The file path to write the image to
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use pinterest_rs::Pinterest;
+    /// use pinterest_rs::download::ImageSize;
+    ///
+    /// # async fn download(pinterest: &Pinterest) -> pinterest_rs::Result<()> {
+    /// let download = pinterest.download_image("https://i.pinimg.com/736x/ab/cd/ef/abcdef.jpg",
+    ///                                         ImageSize::Original, "images/abcdef").await?;
+    /// println!("Saved {} bytes to {:?}", download.len, download.path);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// * `InvalidImageUrl` - If the url is not a pinimg url
+    /// * `ImageUnavailable` - If all the sizes returned 403 or 404
+    pub async fn download_image<P: AsRef<Path>>(&self, image_url: &str, size: ImageSize, path: P) -> crate::Result<Download> {
+        let (size, url, response) = self.fetch_image(image_url, size).await?;
+        let content_type = content_type(&response);
+
+        let mut path = path.as_ref().to_path_buf();
+        if path.extension().is_none() {
+            if let Some(extension) = content_type.as_deref().and_then(extension_for_content_type) {
+                path.set_extension(extension);
+            }
+        }
+
+        if let Some(len) = matches_on_disk(&response, &path)? {
+            #[cfg(feature = "debug")]
+            info!("The file {:?} is already up to date, skipping it", path);
+            return Ok(Download { size, url, content_type, path: Some(path), len, skipped: true });
+        }
+
+        #[cfg(feature = "debug")]
+        info!("Downloading {} into {:?}", url, path);
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first, so an interrupted download never leaves a truncated image
+        let part_path = part_path(&path);
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&part_path)?);
+        let mut response = response;
+        let mut len = 0;
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk)?;
+            len += chunk.len() as u64;
+        }
+        file.flush()?;
+        drop(file);
+        std::fs::rename(&part_path, &path)?;
+
+        Ok(Download { size, url, content_type, path: Some(path), len, skipped: false })
+    }
+
+    /// Downloads a pinterest image at the given size into an [`AsyncWrite`],
+    /// falling back to the next smaller size when the server answers with 403 or 404
+    ///
+    /// # Arguments
+    /// * `image_url` - The pinimg url of the image, at any size
+    /// * `size` - The preferred size to download
+    /// * `writer` - Where to write the image
+    ///
+    /// # Errors
+    /// * `InvalidImageUrl` - If the url is not a pinimg url
+    /// * `ImageUnavailable` - If all the sizes returned 403 or 404
+    pub async fn download_image_to<W: AsyncWrite + Unpin>(&self, image_url: &str, size: ImageSize, writer: &mut W) -> crate::Result<Download> {
+        let (size, url, mut response) = self.fetch_image(image_url, size).await?;
+        let content_type = content_type(&response);
+
+        let mut len = 0;
+        while let Some(chunk) = response.chunk().await? {
+            writer.write_all(&chunk).await?;
+            len += chunk.len() as u64;
+        }
+        writer.flush().await?;
+
+        Ok(Download { size, url, content_type, path: None, len, skipped: false })
+    }
+
+    /// Sends the request for the first available size, starting from the given one
+    async fn fetch_image(&self, image_url: &str, size: ImageSize) -> crate::Result<(ImageSize, Url, Response)> {
+        for size in size.fallbacks() {
+            let url = size.image_url(image_url)?;
+            #[cfg(feature = "debug")]
+            debug!("Trying the image size {:?}: {}", size, url);
+
+            match self.send(self.client.get(url.clone())).await {
+                Ok(response) => return Ok((size, url, response)),
+                // The retry policy may have retried the request before giving up on it
+                Err(_error) if matches!(_error.last_error(), PinterestError::Forbidden(_) | PinterestError::NotFound(_)) => {
+                    #[cfg(feature = "debug")]
+                    debug!("The image size {:?} is not available ({})", size, _error);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(PinterestError::ImageUnavailable(image_url.to_string()))
+    }
+}
+
+#[inline(always)]
+fn content_type(response: &Response) -> Option<String> {
+    response.headers().get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+#[inline(always)]
+fn part_path(path: &Path) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".part");
+    path.with_file_name(file_name)
+}
+
+/// Returns the file length if the file on disk has the same size as the response,
+/// and the same md5 hash as the response `ETag` (the cdn uses the md5 of the content as the etag)
+fn matches_on_disk(response: &Response, path: &Path) -> crate::Result<Option<u64>> {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return Ok(None);
+    };
+
+    let remote_len = response.headers().get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if remote_len != Some(metadata.len()) {
+        return Ok(None);
+    }
+
+    let Some(etag) = response.headers().get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim_start_matches("W/").trim_matches('"').to_ascii_lowercase())
+        .filter(|value| value.len() == 32 && value.bytes().all(|b| b.is_ascii_hexdigit())) else {
+        return Ok(None);
+    };
+
+    let local_hash = format!("{:x}", md5::compute(std::fs::read(path)?));
+    Ok((local_hash == etag).then_some(metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::clock::MockClock;
+    use crate::request::tests::{server, FORBIDDEN, OK};
+    use crate::retry::RetryPolicy;
+
+    const ORIGINAL: &str = "https://i.pinimg.com/originals/ab/cd/ef/abcdef.jpg";
+
+    fn response(headers: &[(&str, &str)]) -> Response {
+        let mut builder = http::Response::builder();
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        Response::from(builder.body("").unwrap())
+    }
+
+    #[test]
+    fn image_url_rewrites_the_size_segment() {
+        assert_eq!(ImageSize::W736.image_url(ORIGINAL).unwrap().as_str(), "https://i.pinimg.com/736x/ab/cd/ef/abcdef.jpg");
+        assert_eq!(ImageSize::Original.image_url("https://i.pinimg.com/564x/ab/cd/ef/abcdef.jpg").unwrap().as_str(), ORIGINAL);
+        assert_eq!(ImageSize::W236.image_url("https://i.pinimg.com/170x/ab/cd/ef/abcdef.jpg?a=b").unwrap().as_str(),
+                   "https://i.pinimg.com/236x/ab/cd/ef/abcdef.jpg?a=b");
+    }
+
+    #[test]
+    fn image_url_rejects_the_urls_without_a_size() {
+        for url in ["not a url", "https://i.pinimg.com/ab/cd/ef/abcdef.jpg", "https://i.pinimg.com/x/abcdef.jpg", "data:image/png,abc"] {
+            assert!(matches!(ImageSize::W474.image_url(url), Err(PinterestError::InvalidImageUrl(_))), "{url}");
+        }
+    }
+
+    #[test]
+    fn fallbacks_go_from_the_size_to_the_smallest() {
+        assert_eq!(ImageSize::Original.fallbacks().collect::<Vec<_>>(), ImageSize::ALL);
+        assert_eq!(ImageSize::W474.fallbacks().collect::<Vec<_>>(), [ImageSize::W474, ImageSize::W236]);
+        assert_eq!(ImageSize::W236.fallbacks().collect::<Vec<_>>(), [ImageSize::W236]);
+    }
+
+    #[test]
+    fn extension_for_content_type_ignores_the_parameters_and_the_case() {
+        assert_eq!(extension_for_content_type("image/jpeg"), Some("jpg"));
+        assert_eq!(extension_for_content_type("Image/PNG; charset=binary"), Some("png"));
+        assert_eq!(extension_for_content_type(" video/mp4 "), Some("mp4"));
+        assert_eq!(extension_for_content_type("application/vnd.apple.mpegurl"), Some("m3u8"));
+        assert_eq!(extension_for_content_type("text/html"), None);
+        assert_eq!(extension_for_content_type(""), None);
+    }
+
+    #[test]
+    fn matches_on_disk_compares_the_length_and_the_etag() {
+        let path = std::env::temp_dir().join(format!("pinterest_rs_download_test_{}.jpg", std::process::id()));
+        let content = b"not really an image";
+        std::fs::write(&path, content).unwrap();
+        let hash = format!("{:x}", md5::compute(content));
+        let len = content.len().to_string();
+
+        let quoted = format!("\"{}\"", hash.to_ascii_uppercase());
+        assert_eq!(matches_on_disk(&response(&[("content-length", &len), ("etag", &quoted)]), &path).unwrap(),
+                   Some(content.len() as u64));
+        let weak = format!("W/\"{hash}\"");
+        assert!(matches_on_disk(&response(&[("content-length", &len), ("etag", &weak)]), &path).unwrap().is_some());
+
+        // A different length, a different hash, an etag that isn't an md5 hash or no etag at all
+        assert_eq!(matches_on_disk(&response(&[("content-length", "1"), ("etag", &hash)]), &path).unwrap(), None);
+        let other = format!("{:x}", md5::compute(b"another image"));
+        assert_eq!(matches_on_disk(&response(&[("content-length", &len), ("etag", &other)]), &path).unwrap(), None);
+        assert_eq!(matches_on_disk(&response(&[("content-length", &len), ("etag", "abc")]), &path).unwrap(), None);
+        assert_eq!(matches_on_disk(&response(&[("content-length", &len)]), &path).unwrap(), None);
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(matches_on_disk(&response(&[("content-length", &len), ("etag", &hash)]), &path).unwrap(), None);
+    }
+
+    /// Retries every error once
+    struct RetryOnce;
+
+    impl RetryPolicy for RetryOnce {
+        fn retry_delay(&self, attempt: u32, _error: &PinterestError) -> Option<Duration> {
+            (attempt == 0).then_some(Duration::from_secs(1))
+        }
+    }
+
+    async fn fetch_image_falls_back_after_the_retries() {
+        let url = server(vec![FORBIDDEN, FORBIDDEN, OK]);
+        #[cfg(not(feature = "credentials"))]
+        let pinterest = Pinterest::new("username", None);
+        #[cfg(feature = "credentials")]
+        let pinterest = Pinterest::new("username", "csrftoken", None);
+        let pinterest = pinterest.with_retry_policy(RetryOnce).with_clock(MockClock::new());
+
+        let (size, url, _) = pinterest.fetch_image(&format!("{url}originals/ab/cd.jpg"), ImageSize::Original).await.unwrap();
+        assert_eq!(size, ImageSize::W736);
+        assert!(url.path().starts_with("/736x/"));
+    }
+
+    #[async_std::test]
+    async fn fetch_image_falls_back_after_the_retries_on_async_std() {
+        fetch_image_falls_back_after_the_retries().await;
+    }
+
+    #[tokio::test]
+    async fn fetch_image_falls_back_after_the_retries_on_tokio() {
+        fetch_image_falls_back_after_the_retries().await;
+    }
+}
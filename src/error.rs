@@ -0,0 +1,171 @@
+use std::time::Duration;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Response, StatusCode};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "debug")]
+use log::*;
+
+use crate::PinterestError;
+
+/// The error returned by the pinterest api, parsed from the `resource_response` of a failed request
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ApiError {
+    /// The http status of the response
+    pub http_status: u16,
+    /// The pinterest error code, if any
+    pub code: Option<i64>,
+    /// The error message, if any
+    pub message: Option<String>,
+    /// The `api_error_code` of the error, if any
+    pub api_error_code: Option<i64>,
+    /// The name of the resource that failed, like `BoardResource`
+    pub resource: Option<String>,
+    /// The options that were sent to the resource
+    pub options: Option<serde_json::Value>,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HTTP {}", self.http_status)?;
+        if let Some(resource) = &self.resource {
+            write!(f, " from {resource}")?;
+        }
+        if let Some(code) = self.code {
+            write!(f, " (code {code})")?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The subset of the pinterest response body that describes the error
+#[derive(Deserialize)]
+struct ErrorBody {
+    resource_response: Option<ResourceResponse>,
+    resource: Option<Resource>,
+}
+
+#[derive(Deserialize)]
+struct ResourceResponse {
+    code: Option<i64>,
+    message: Option<String>,
+    error: Option<ResourceError>,
+}
+
+#[derive(Deserialize)]
+struct ResourceError {
+    code: Option<i64>,
+    message: Option<String>,
+    api_error_code: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct Resource {
+    name: Option<String>,
+    options: Option<serde_json::Value>,
+}
+
+impl ApiError {
+    /// Parses the api error from the status and the body of a failed response,
+    /// the body fields are left empty if it's not a pinterest error body
+    pub fn from_body(status: StatusCode, body: &str) -> Self {
+        let mut error = ApiError {
+            http_status: status.as_u16(),
+            ..Default::default()
+        };
+
+        let Ok(body) = serde_json::from_str::<ErrorBody>(body) else {
+            let body = body.trim();
+            if !body.is_empty() && !body.starts_with('<') {
+                error.message = Some(body.to_string());
+            }
+            return error;
+        };
+
+        if let Some(response) = body.resource_response {
+            let inner = response.error;
+            error.code = inner.as_ref().and_then(|e| e.code).or(response.code);
+            error.message = inner.as_ref().and_then(|e| e.message.clone()).or(response.message);
+            error.api_error_code = inner.and_then(|e| e.api_error_code);
+        }
+        if let Some(resource) = body.resource {
+            error.resource = resource.name;
+            error.options = resource.options;
+        }
+
+        error
+    }
+
+    #[inline(always)]
+    fn is_csrf_failure(&self) -> bool {
+        self.message.as_deref().is_some_and(|message| message.to_ascii_lowercase().contains("csrf"))
+    }
+}
+
+impl PinterestError {
+    /// Classifies an api error into the dedicated variants, so callers can match on them
+    pub fn from_api_error(error: ApiError, retry_after: Option<Duration>) -> Self {
+        let error = Box::new(error);
+        match StatusCode::from_u16(error.http_status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR) {
+            StatusCode::UNAUTHORIZED => PinterestError::Unauthorized(error),
+            StatusCode::FORBIDDEN if error.is_csrf_failure() => PinterestError::BadCsrf(error),
+            StatusCode::FORBIDDEN => PinterestError::Forbidden(error),
+            StatusCode::NOT_FOUND => PinterestError::NotFound(error),
+            StatusCode::TOO_MANY_REQUESTS => PinterestError::RateLimited { retry_after, error },
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => PinterestError::ValidationError(error),
+            _ => PinterestError::ApiError(error),
+        }
+    }
+
+    /// Returns the error of the last attempt when the retries ran out, or this error otherwise,
+    /// useful to match on the cause of a failed request whether it was retried or not
+    pub fn last_error(&self) -> &PinterestError {
+        match self {
+            PinterestError::RetriesExhausted { last_error, .. } => last_error,
+            error => error,
+        }
+    }
+
+    /// Returns the parsed api error, if this error came from a failed api response
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            PinterestError::ApiError(error)
+            | PinterestError::NotFound(error)
+            | PinterestError::Unauthorized(error)
+            | PinterestError::Forbidden(error)
+            | PinterestError::BadCsrf(error)
+            | PinterestError::ValidationError(error)
+            | PinterestError::RateLimited { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the `Retry-After` header of the response, only the delay in seconds form is supported
+pub(crate) fn retry_after(response: &Response) -> Option<Duration> {
+    response.headers().get(RETRY_AFTER)?
+        .to_str().ok()?
+        .trim().parse().ok()
+        .map(Duration::from_secs)
+}
+
+/// Returns the response if its status is a success, or the parsed api error otherwise
+pub(crate) async fn check_response(response: Response) -> crate::Result<Response> {
+    let status = response.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return Ok(response);
+    }
+
+    let retry_after = retry_after(&response);
+    let body = response.text().await.unwrap_or_default();
+    let error = ApiError::from_body(status, &body);
+
+    #[cfg(feature = "debug")] {
+        debug!("The request failed: {}", error);
+        trace!("The response body: {}", body);
+    }
+
+    Err(PinterestError::from_api_error(error, retry_after))
+}
@@ -0,0 +1,68 @@
+use std::path::Path;
+use futures::{AsyncWrite, AsyncWriteExt};
+use reqwest::Response;
+
+/// A file opened for writing with the file api of the current runtime
+pub(crate) type AsyncFile = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Creates or truncates a file with the tokio file api when called from a tokio runtime,
+/// then with the async-std one, or with a blocking file when no runtime feature is enabled
+pub(crate) async fn create_file(path: &Path) -> std::io::Result<AsyncFile> {
+    #[cfg(feature = "tokio-runtime")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        use tokio_util::compat::TokioAsyncWriteCompatExt;
+        return Ok(Box::new(tokio::fs::File::create(path).await?.compat_write()));
+    }
+
+    #[cfg(feature = "async-std-runtime")]
+    return Ok(Box::new(async_std::fs::File::create(path).await?));
+
+    #[cfg(not(feature = "async-std-runtime"))]
+    Ok(Box::new(futures::io::AllowStdIo::new(std::fs::File::create(path)?)))
+}
+
+/// Creates a directory and its missing parents with the file api of the current runtime, see [`create_file`]
+pub(crate) async fn create_dir_all(path: &Path) -> std::io::Result<()> {
+    #[cfg(feature = "tokio-runtime")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return tokio::fs::create_dir_all(path).await;
+    }
+
+    #[cfg(feature = "async-std-runtime")]
+    return async_std::fs::create_dir_all(path).await;
+
+    #[cfg(not(feature = "async-std-runtime"))]
+    std::fs::create_dir_all(path)
+}
+
+/// Writes a whole file through a temporary file, so an interrupted write never leaves a truncated file
+pub(crate) async fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".part");
+    let part_path = path.with_file_name(file_name);
+
+    let mut file = create_file(&part_path).await?;
+    file.write_all(contents).await?;
+    file.close().await?;
+
+    #[cfg(feature = "tokio-runtime")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return tokio::fs::rename(part_path, path).await;
+    }
+
+    #[cfg(feature = "async-std-runtime")]
+    return async_std::fs::rename(part_path, path).await;
+
+    #[cfg(not(feature = "async-std-runtime"))]
+    std::fs::rename(part_path, path)
+}
+
+/// Writes the body of the response chunk by chunk, without buffering it whole, returns the written length
+pub(crate) async fn write_response<W: AsyncWrite + Unpin>(mut response: Response, writer: &mut W) -> crate::Result<u64> {
+    let mut len = 0;
+    while let Some(chunk) = response.chunk().await? {
+        writer.write_all(&chunk).await?;
+        len += chunk.len() as u64;
+    }
+    Ok(len)
+}
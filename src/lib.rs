@@ -1,50 +1,146 @@
+use std::sync::Arc;
+use reqwest::cookie::Jar;
+use reqwest::header::HeaderMap;
+#[cfg(feature = "credentials")]
 use crate::utils::build_request_headers;
+use crate::clock::{Clock, SystemClock};
+use crate::proxy::ProxyPool;
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
 
 #[cfg(feature = "login")]
 pub mod login;
+#[cfg(feature = "login")]
+pub mod pool;
+#[cfg(feature = "login")]
+pub mod session;
 mod utils;
+mod error;
+mod fs;
+mod request;
+mod resource;
+pub mod board;
+pub mod pin;
+pub mod user;
+pub mod mirror;
+pub mod clock;
+#[cfg(feature = "login")]
+pub mod credentials;
+pub mod proxy;
+pub mod rate_limit;
+pub mod retry;
+pub mod video;
+pub mod download;
+
+pub use crate::error::ApiError;
+#[cfg(feature = "login")]
+pub use pinterest_login::cookie::SessionCookie;
 
 pub const PINTEREST_BASE_URL: &str = "https://www.pinterest.com";
 const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) \
-            Chrome/91.0.4472.114 Safari/537.36";
+            Chrome/141.0.0.0 Safari/537.36";
 
 
 pub struct Pinterest {
     client: reqwest::Client,
+    cookie_jar: Arc<Jar>,
+    headers: HeaderMap,
     username: String,
     #[cfg(feature = "credentials")]
     crf_token: String,
     #[cfg(feature = "login")]
     user_agent: Option<String>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    retry_policy: Option<Arc<dyn RetryPolicy>>,
+    clock: Arc<dyn Clock>,
+    proxy_pool: Option<ProxyPool>,
+    #[cfg(feature = "login")]
+    session: Vec<pinterest_login::cookie::SessionCookie>,
+    #[cfg(feature = "login")]
+    login_options: pinterest_login::LoginOptions,
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum PinterestError {
     #[cfg(feature = "login")]
     #[error("{0}")]
-    LoginError(#[from] pinterest_login::PinterestLoginError),
-    #[cfg(feature = "login")]
+    LoginError(Box<pinterest_login::PinterestLoginError>),
     #[error("The crf token was not found in the cookies")]
     MissingCrfToken,
     /*#[error("There was an error parsing the url: {0}")]
     UrlError(#[from] url::ParseError),*/
-    #[cfg(feature = "login")]
-    #[error("There was an error reading the credentials file: {0}")]
+    #[error("There was an IO error: {0}")]
     IoError(#[from] std::io::Error),
-    #[cfg(feature = "login")]
     #[error("There was an error deserializing the credentials file")]
     InvalidCredentialsFile,
-    #[cfg(feature = "login")]
     #[error("The credentials file path does not exist")]
     CredPathNotExists,
+    #[error("There is no saved session in the credential store")]
+    SessionNotFound,
+    #[error("The cookies are malformed: {0}")]
+    InvalidCookies(String),
+    #[cfg(feature = "encryption")]
+    #[error("The credentials could not be decrypted, the passphrase may be wrong")]
+    CredentialsDecryptionError,
+    #[cfg(feature = "encryption")]
+    #[error("The credentials could not be encrypted")]
+    CredentialsEncryptionError,
+    #[cfg(feature = "encryption")]
+    #[error("The encryption key could not be derived from the passphrase: {0}")]
+    KeyDerivationError(String),
     #[error("There was an error sending the request: {0}")]
     RequestError(#[from] reqwest::Error),
     #[error("There was an error parsing the response: {0}")]
     ResponseError(#[from] serde_json::Error),
+    #[error("The pinterest api returned an error: {0}")]
+    ApiError(Box<ApiError>),
+    #[error("The requested resource was not found: {0}")]
+    NotFound(Box<ApiError>),
+    #[error("The session is not authenticated or has expired: {0}")]
+    Unauthorized(Box<ApiError>),
+    #[error("The access to the resource is forbidden: {0}")]
+    Forbidden(Box<ApiError>),
+    #[error("The request was rate limited (retry after {retry_after:?}): {error}")]
+    RateLimited {
+        retry_after: Option<std::time::Duration>,
+        error: Box<ApiError>,
+    },
+    #[error("The crf token was rejected: {0}")]
+    BadCsrf(Box<ApiError>),
+    #[error("The request was rejected as invalid: {0}")]
+    ValidationError(Box<ApiError>),
+    #[error("The HLS playlist is invalid or unsupported: {0}")]
+    InvalidPlaylist(String),
+    #[error("The board is not a username/board-name path or a board url: {0}")]
+    InvalidBoard(String),
+    #[error("The url is not a pinterest image url: {0}")]
+    InvalidImageUrl(String),
+    #[error("The image is not available in any size: {0}")]
+    ImageUnavailable(String),
+    #[error("The rate limit must be a positive number of requests per second, got {0}")]
+    InvalidRateLimit(f64),
+    #[error("The proxy url is invalid: {0}")]
+    InvalidProxy(String),
+    #[error("All the proxies of the pool were evicted")]
+    NoProxyAvailable,
+    #[error("All the accounts of the pool are expired or the pool is empty")]
+    NoAccountAvailable,
+    #[error("The request failed after {attempts} attempts: {last_error}")]
+    RetriesExhausted {
+        attempts: u32,
+        last_error: Box<PinterestError>,
+    },
 }
 
 pub type Result<T> = std::result::Result<T, PinterestError>;
 
+#[cfg(feature = "login")]
+impl From<pinterest_login::PinterestLoginError> for PinterestError {
+    fn from(error: pinterest_login::PinterestLoginError) -> Self {
+        Self::LoginError(Box::new(error))
+    }
+}
+
 
 impl Pinterest {
     pub fn new<S: Into<String> + Copy>(
@@ -53,23 +149,143 @@ impl Pinterest {
         user_agent: Option<S>,
     ) -> Self {
         #[cfg(feature = "credentials")]
-            let header_map = build_request_headers(crf_token.into(), user_agent.map(|s| s.into()));
-
-        #[cfg(feature = "credentials")]
-            let client = reqwest::Client::builder()
-            .default_headers(header_map)
-            .build().expect("Failed to build request client");
+            let headers = build_request_headers(crf_token.into(), user_agent.map(|s| s.into()))
+                .expect("The crf token is not a valid header value");
 
         #[cfg(not(feature = "credentials"))]
-            let client = reqwest::Client::new();
+            let headers = HeaderMap::new();
+
+        let cookie_jar = Arc::new(Jar::default());
+        let client = reqwest::Client::builder()
+            .cookie_provider(cookie_jar.clone())
+            .default_headers(headers.clone())
+            .build().expect("Failed to build request client");
 
         Self {
             client,
+            cookie_jar,
+            headers,
             username: username.into(),
             #[cfg(feature = "credentials")]
             crf_token: crf_token.into(),
             #[cfg(feature = "login")]
             user_agent: user_agent.map(|s| s.into()),
+            rate_limiter: None,
+            retry_policy: None,
+            clock: Arc::new(SystemClock),
+            proxy_pool: None,
+            #[cfg(feature = "login")]
+            session: Vec::new(),
+            #[cfg(feature = "login")]
+            login_options: pinterest_login::LoginOptions::default(),
         }
     }
+
+    /// Creates a client without a crf token, for the constructors that set it from the cookies
+    #[cfg(feature = "login")]
+    pub(crate) fn with_username(username: &str) -> Self {
+        #[cfg(not(feature = "credentials"))]
+        return Self::new(username, None);
+        #[cfg(feature = "credentials")]
+        return Self::new(username, "", None);
+    }
+
+    /// Sends the requests through a pool of proxies, see [`ProxyPool`]
+    ///
+    /// # Errors
+    /// * `RequestError` - If a client can't be built for a proxy
+    pub fn with_proxy_pool(mut self, mut proxy_pool: ProxyPool) -> crate::Result<Self> {
+        proxy_pool.clock = self.clock.clone();
+        self.proxy_pool = Some(proxy_pool);
+        self.rebuild_clients()?;
+        Ok(self)
+    }
+
+    /// Returns the proxy pool, to check the health statistics of the proxies
+    pub fn proxy_pool(&self) -> Option<&ProxyPool> {
+        self.proxy_pool.as_ref()
+    }
+
+    /// Rebuilds the request clients from the cookie jar and the headers, one client per proxy
+    pub(crate) fn rebuild_clients(&mut self) -> crate::Result<()> {
+        let builder = || reqwest::Client::builder()
+            .cookie_provider(self.cookie_jar.clone())
+            .default_headers(self.headers.clone());
+
+        let client = builder().build()?;
+        if let Some(proxy_pool) = &mut self.proxy_pool {
+            for entry in &mut proxy_pool.entries {
+                entry.client = Some(builder().proxy(entry.proxy.clone()).build()?);
+            }
+        }
+        self.client = client;
+
+        Ok(())
+    }
+
+    /// Limits the rate of the requests sent by this client, the requests wait until they are allowed
+    ///
+    /// # Example
+    /// ```
+    /// # use pinterest_rs::Pinterest;
+    /// use pinterest_rs::rate_limit::RateLimiter;
+    /// use pinterest_rs::retry::DefaultRetryPolicy;
+    ///
+    /// # fn setup(pinterest: Pinterest) -> Pinterest {
+    /// pinterest
+    ///     .with_rate_limiter(RateLimiter::default())
+    ///     .with_retry_policy(DefaultRetryPolicy::default())
+    /// # }
+    /// ```
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(Arc::new(rate_limiter));
+        self
+    }
+
+    /// Retries the failed requests according to the given policy,
+    /// by default the failed requests are not retried
+    pub fn with_retry_policy(mut self, retry_policy: impl RetryPolicy + 'static) -> Self {
+        self.retry_policy = Some(Arc::new(retry_policy));
+        self
+    }
+
+    /// Replaces the clock used by the rate limiter, the retry policy and the proxy pool,
+    /// useful to test them with a mock clock
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        if let Some(proxy_pool) = &mut self.proxy_pool {
+            proxy_pool.clock = self.clock.clone();
+        }
+        self
+    }
+
+    /// Sets the options of the browser login flow, like the directory to save the diagnostics to when the login fails
+    ///
+    /// # Example
+    /// ```
+    /// # use pinterest_rs::Pinterest;
+    /// use pinterest_login::LoginOptions;
+    ///
+    /// # fn setup(pinterest: Pinterest) -> Pinterest {
+    /// let pinterest = pinterest
+    ///     .with_login_options(LoginOptions::new().with_diagnostics_dir("login-diagnostics"));
+    /// # pinterest
+    /// # }
+    /// ```
+    #[cfg(feature = "login")]
+    pub fn with_login_options(mut self, login_options: pinterest_login::LoginOptions) -> Self {
+        self.login_options = login_options;
+        self
+    }
+
+    /// Returns the username of the account this client was created for
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Returns the crf token sent in the `X-CSRFToken` header
+    #[cfg(feature = "credentials")]
+    pub fn crf_token(&self) -> &str {
+        &self.crf_token
+    }
 }
@@ -1,111 +1,259 @@
 use std::path::Path;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 use reqwest::cookie::Jar;
 use url::Url;
 use pinterest_login::config_builder::{BrowserConfigBuilder, DefaultBrowserConfigBuilder};
+use pinterest_login::cookie::SessionCookie;
 use pinterest_login::login_bot::{BrowserLoginBot, DefaultBrowserLoginBot};
+use pinterest_login::LoginOptions;
+#[cfg(feature = "http-login")]
+use pinterest_login::http_login::HttpLoginOptions;
 #[cfg(feature = "debug")]
 use log::*;
 
-use crate::{Pinterest, PINTEREST_BASE_URL, PinterestError};
+use crate::{Pinterest, PinterestError, DEFAULT_USER_AGENT};
+use crate::credentials::{CredentialStore, FileCredentialStore};
+use crate::proxy::browser_proxy_server;
 use crate::utils::build_request_headers;
 
+/// The cookies that carry the authenticated session
+const SESSION_COOKIE_NAMES: [&str; 2] = ["_pinterest_sess", "_auth"];
+
 impl Pinterest {
     #[inline]
-    pub async fn login<S: Into<String>, P: AsRef<Path>>(&mut self, email: S, password: S, cred_path: Option<P>) -> crate::Result<()> {
+    pub async fn login<S: Into<String>, C: CredentialStore>(&mut self, email: S, password: S, cred_store: Option<C>) -> crate::Result<()> {
         self.login_with_custom_bot_and_config(&DefaultBrowserLoginBot::new(email.into().as_str(), password.into().as_str()),
-                                              &DefaultBrowserConfigBuilder::new(true, Some(Duration::from_secs(8)), None),
-                                              cred_path).await
+                                              &self.browser_config(Some(Duration::from_secs(8)), None),
+                                              cred_store).await
+    }
+
+    /// Logs in over plain http, without launching a browser, and falls back to the browser login
+    /// when pinterest returns a challenge, see [`pinterest_login::http_login::http_login_or_browser`]
+    ///
+    /// The requests use the user agent and the proxy of the client
+    ///
+    /// # Arguments
+    /// * `email` - The account email, for the http login
+    /// * `password` - The account password, for the http login
+    /// * `bot` - The login bot of the browser fallback, with a two-factor code provider when the account needs one
+    /// * `cred_store` - Where to save the session cookies, if any
+    #[cfg(feature = "http-login")]
+    pub async fn login_over_http<S: Into<String>, C: CredentialStore>(&mut self, email: S, password: S, bot: &impl BrowserLoginBot,
+                                                                      cred_store: Option<C>) -> crate::Result<()> {
+        let (email, password) = (email.into(), password.into());
+        let mut http_options = HttpLoginOptions::new()
+            .with_user_agent(self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT));
+        if let Some(proxy) = self.proxy_pool.as_ref().and_then(|pool| pool.current()) {
+            http_options = http_options.with_proxy(proxy);
+        }
+        #[cfg(feature = "debug")] info!("Logging in over http");
+
+        let cookies = pinterest_login::http_login::http_login_or_browser(&email, &password, &http_options, bot,
+                                                                          &self.browser_config(Some(Duration::from_secs(8)), None),
+                                                                          &self.aligned_login_options()).await?;
+        self.set_login_cookies(cookies, cred_store)
+    }
+
+    /// Builds the default browser config, with the proxy of the pool (if any)
+    /// so the browser login and the api calls leave from the same ip
+    #[inline(always)]
+    fn browser_config(&self, request_timeout: Option<Duration>, launch_timeout: Option<Duration>) -> DefaultBrowserConfigBuilder {
+        let config = DefaultBrowserConfigBuilder::new(true, request_timeout, launch_timeout);
+        match self.proxy_pool.as_ref().and_then(|pool| pool.current()) {
+            Some(proxy) => {
+                #[cfg(feature = "debug")] debug!("Logging in through the proxy: {}", proxy);
+                config.with_proxy(browser_proxy_server(proxy))
+            }
+            None => config,
+        }
     }
 
     #[inline(always)]
-    pub async fn login_with_custom_bot_and_config<P: AsRef<Path>>(&mut self, bot: &impl BrowserLoginBot,
-                                                                  config: &impl BrowserConfigBuilder,
-                                                                  cred_path: Option<P>) -> crate::Result<()> {
+    pub async fn login_with_custom_bot_and_config<C: CredentialStore>(&mut self, bot: &impl BrowserLoginBot,
+                                                                      config: &impl BrowserConfigBuilder,
+                                                                      cred_store: Option<C>) -> crate::Result<()> {
         #[cfg(feature = "debug")] info!("Logging in with custom bot and config");
 
-        let cookies_map = pinterest_login::login(bot, config).await?;
+        let cookies = pinterest_login::login_with_options(bot, config, &self.aligned_login_options()).await?;
+        self.set_login_cookies(cookies, cred_store)
+    }
+
+    /// Logs in with an already running browser, like a shared headless-shell container, instead of launching one
+    ///
+    /// # Arguments
+    /// * `bot` - The login bot to use to fill and submit the login form
+    /// * `debug_ws_url` - The DevTools websocket url of the browser, see [`pinterest_login::connect_and_login`]
+    /// * `cred_store` - Where to save the session cookies, if any
+    pub async fn login_with_remote_browser<C: CredentialStore>(&mut self, bot: &impl BrowserLoginBot, debug_ws_url: &str,
+                                                                cred_store: Option<C>) -> crate::Result<()> {
+        #[cfg(feature = "debug")] info!("Logging in with the remote browser: {}", debug_ws_url);
 
+        let cookies = pinterest_login::connect_and_login(debug_ws_url, bot, &self.aligned_login_options()).await?;
+        self.set_login_cookies(cookies, cred_store)
+    }
+
+    /// Returns the login options, with the stealth user agent aligned with the one of the api requests,
+    /// so the session is not used from two different browsers
+    fn aligned_login_options(&self) -> LoginOptions {
+        match self.login_options.stealth() {
+            Some(stealth) if stealth.user_agent().is_none() => {
+                let user_agent = self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+                #[cfg(feature = "debug")] debug!("Using the user agent of the client for the stealth mode: {}", user_agent);
+                self.login_options.clone().with_stealth(stealth.clone().with_user_agent(user_agent))
+            }
+            _ => self.login_options.clone(),
+        }
+    }
+
+    /// Saves the cookies returned by the login to the credentials store, and sets up the client with them
+    fn set_login_cookies<C: CredentialStore>(&mut self, cookies: Vec<SessionCookie>, cred_store: Option<C>) -> crate::Result<()> {
         #[cfg(feature = "debug")] {
             info!("Successfully logged in");
-            trace!("Cookies: {:?}", cookies_map);
-            debug!("Cookies count: {}", cookies_map.len());
-            debug!("Keys: {:?}", cookies_map.keys());
+            trace!("Cookies: {:?}", cookies);
+            debug!("Cookies count: {}", cookies.len());
+            debug!("Names: {:?}", cookies.iter().map(|c| &c.name).collect::<Vec<_>>());
         }
 
-        // Get the crf token from the cookies
-        let Some(crf_token) = cookies_map.get("csrftoken").map(|s| s.to_owned()) else {
-            #[cfg(feature = "debug")] error!("The crf token was not found in the cookies");
-            return Err(PinterestError::MissingCrfToken);
-        };
+        let crf_token = crf_token(&cookies)?;
 
-        #[cfg(feature = "debug")] {
-            trace!("Crf token: {}", crf_token);
-            debug!("Crf token length: {}", crf_token.len());
-            debug!("Credentials path is set: {}", cred_path.is_some());
+        #[cfg(feature = "debug")]
+        debug!("Credentials store is set: {}", cred_store.is_some());
+
+        if let Some(cred_store) = cred_store {
+            #[cfg(feature = "debug")] info!("Saving the cookies to the credentials store");
+            cred_store.save(&cookies)?;
+            #[cfg(feature = "debug")] info!("Successfully saved the cookies to the credentials store");
         }
 
-        if let Some(cred_path) = cred_path {
-            #[cfg(feature = "debug")] {
-                info!("Saving the cookies to the credentials file");
-                trace!("Credentials path: {:?}", cred_path.as_ref());
-            }
-            // Save the cookies to the credentials file as a json string
-            let cookies_json = serde_json::to_string(&cookies_map).unwrap();
-            #[cfg(feature = "debug")] trace!("Cookies json: {}", cookies_json);
-            std::fs::write(cred_path, cookies_json)?;
-            #[cfg(feature = "debug")] info!("Successfully saved the cookies to the credentials file");
+        self.set_session_cookies(cookies, crf_token)
+    }
+
+    /// Loads the session cookies from a credentials file saved by the login methods,
+    /// so the client can be used without logging in again
+    ///
+    /// # Arguments
+    /// * `cred_path` - The path of the credentials file
+    ///
+    /// # Errors
+    /// * `CredPathNotExists` - If the credentials file does not exist
+    /// * `InvalidCredentialsFile` - If the credentials file can't be deserialized
+    /// * `MissingCrfToken` - If the crf token is not in the saved cookies
+    pub fn load_credentials<P: AsRef<Path>>(&mut self, cred_path: P) -> crate::Result<()> {
+        match self.load_session(FileCredentialStore::new(cred_path)) {
+            Err(PinterestError::SessionNotFound) => Err(PinterestError::CredPathNotExists),
+            result => result,
         }
+    }
+
+    /// Loads the session cookies from a credentials store,
+    /// so the client can be used without logging in again
+    ///
+    /// # Arguments
+    /// * `cred_store` - The credentials store the session was saved to by the login methods
+    ///
+    /// # Errors
+    /// * `SessionNotFound` - If there is no saved session in the store
+    /// * `MissingCrfToken` - If the crf token is not in the saved cookies
+    /// * Any error returned by the store
+    pub fn load_session<C: CredentialStore>(&mut self, cred_store: C) -> crate::Result<()> {
+        #[cfg(feature = "debug")] info!("Loading the cookies from the credentials store");
 
+        let Some(cookies) = cred_store.load()? else {
+            #[cfg(feature = "debug")] error!("There is no saved session in the credentials store");
+            return Err(PinterestError::SessionNotFound);
+        };
+
+        let crf_token = crf_token(&cookies)?;
+        self.set_session_cookies(cookies, crf_token)
+    }
+
+    /// Replaces the cookie jar with the session cookies, and rebuilds the clients with the crf token header
+    pub(crate) fn set_session_cookies(&mut self, cookies: Vec<SessionCookie>, crf_token: String) -> crate::Result<()> {
         #[cfg(feature = "debug")] info!("Setting up the client with the cookies");
 
-        // Setup the client with the cookies
+        // Setup the client with the cookies, the jar drops them when they expire
         let jar = Jar::default();
+        let now = SystemTime::now();
 
-        let mut cookies_str = String::new();
-        for cookie in cookies_map {
-            cookies_str.push_str(&cookie.0);
-            cookies_str.push('=');
-            cookies_str.push_str(&cookie.1);
-            cookies_str.push(';');
+        for cookie in &cookies {
+            let Ok(url) = Url::parse(&cookie.url()) else {
+                #[cfg(feature = "debug")] warn!("Skipping the cookie {} with the invalid domain: {}", cookie.name, cookie.domain);
+                continue;
+            };
+            let set_cookie = cookie.to_set_cookie_string(now);
+            #[cfg(feature = "debug")] trace!("Adding the cookie to the cookie jar for {}: {}", url, set_cookie);
+            jar.add_cookie_str(&set_cookie, &url);
         }
 
         #[cfg(feature = "debug")] {
-            debug!("Cookies string length: {}", cookies_str.len());
-            trace!("Cookies string: {}", cookies_str);
-            debug!("Adding the cookies to the cookie jar with the base url: {}", PINTEREST_BASE_URL);
-        }
-
-        jar.add_cookie_str(&cookies_str, &Url::parse(PINTEREST_BASE_URL).unwrap());
-
-        #[cfg(feature = "debug")] {
-            debug!("Building the client with the cookie jar and the request headers");
+            debug!("Building the clients with the cookie jar and the request headers");
             trace!("User agent: {:?}", self.user_agent);
         }
 
-        self.client = reqwest::Client::builder()
-            .cookie_provider(jar.into())
-            .default_headers(build_request_headers(
-                crf_token,
-                self.user_agent.clone(),
-            ))
-            .build()?;
+        // Built before changing the client, so an invalid crf token leaves it as it was
+        let headers = build_request_headers(
+            crf_token,
+            self.user_agent.clone(),
+        )?;
+        self.cookie_jar = Arc::new(jar);
+        self.session = cookies;
+        self.headers = headers;
+        self.rebuild_clients()?;
 
         Ok(())
     }
 
+    /// Returns the session cookies, with all their attributes
+    pub fn session_cookies(&self) -> &[SessionCookie] {
+        &self.session
+    }
+
+    /// Returns when the session will lapse, the earliest expiry of the `_pinterest_sess` and `_auth` cookies
+    ///
+    /// Returns `None` if the client is not logged in, or if the session cookies have no expiry
+    pub fn session_expires_at(&self) -> Option<SystemTime> {
+        self.session.iter()
+            .filter(|cookie| SESSION_COOKIE_NAMES.contains(&cookie.name.as_str()))
+            .filter_map(SessionCookie::expires_at)
+            .min()
+    }
+
+    /// Returns true if one of the session cookies is expired
+    pub fn is_session_expired(&self) -> bool {
+        self.session_expires_at().is_some_and(|expires_at| expires_at <= SystemTime::now())
+    }
+
     #[inline]
-    pub async fn login_with_timeout<S: Into<String>, P: AsRef<Path>>(&mut self, email: S, password: S,
-                                                                     request_timeout: Duration,
-                                                                     browser_launch_timeout: Duration,
-                                                                     cred_path: Option<P>) -> crate::Result<()> {
+    pub async fn login_with_timeout<S: Into<String>, C: CredentialStore>(&mut self, email: S, password: S,
+                                                                        request_timeout: Duration,
+                                                                        browser_launch_timeout: Duration,
+                                                                        cred_store: Option<C>) -> crate::Result<()> {
         #[cfg(feature = "debug")] {
             trace!("Request timeout: {:?}", request_timeout);
             trace!("Browser launch timeout: {:?}", browser_launch_timeout);
         }
 
         self.login_with_custom_bot_and_config(&DefaultBrowserLoginBot::new(email.into().as_str(), password.into().as_str()),
-                                              &DefaultBrowserConfigBuilder::new(true, Some(request_timeout), Some(browser_launch_timeout)),
-                                              cred_path).await
+                                              &self.browser_config(Some(request_timeout), Some(browser_launch_timeout)),
+                                              cred_store).await
     }
 }
+
+/// Gets the crf token from the cookies, the expired cookies are ignored
+#[inline(always)]
+pub(crate) fn crf_token(cookies: &[SessionCookie]) -> crate::Result<String> {
+    let Some(crf_token) = cookies.iter()
+        .find(|cookie| cookie.name == "csrftoken" && !cookie.is_expired())
+        .map(|cookie| cookie.value.to_owned()) else {
+        #[cfg(feature = "debug")] error!("The crf token was not found in the cookies");
+        return Err(PinterestError::MissingCrfToken);
+    };
+
+    #[cfg(feature = "debug")] {
+        trace!("Crf token: {}", crf_token);
+        debug!("Crf token length: {}", crf_token.len());
+    }
+
+    Ok(crf_token)
+}
@@ -0,0 +1,444 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "debug")]
+use log::*;
+
+use crate::board::{Board, BoardSection};
+use crate::download::ImageSize;
+use crate::pin::Pin;
+use crate::video::{VideoFormat, VideoPreference};
+use crate::{fs, Pinterest, PinterestError};
+
+/// The name of the manifest file, in the mirror directory
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// The manifest is saved every time this many pins were downloaded, so an interrupted run keeps its progress
+const MANIFEST_SAVE_INTERVAL: usize = 10;
+
+/// The options of [`Pinterest::mirror_board`]
+///
+/// # Example
+/// ```
+/// use pinterest_rs::download::ImageSize;
+/// use pinterest_rs::mirror::MirrorOptions;
+///
+/// let options = MirrorOptions::new()
+///     .with_concurrency(8)
+///     .with_image_size(ImageSize::W736)
+///     .with_videos(false);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MirrorOptions {
+    concurrency: usize,
+    image_size: ImageSize,
+    video_preference: VideoPreference,
+    videos: bool,
+}
+
+impl Default for MirrorOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            image_size: ImageSize::Original,
+            video_preference: VideoPreference::Format(VideoFormat::Mp4),
+            videos: true,
+        }
+    }
+}
+
+impl MirrorOptions {
+    /// Creates the default options: 4 downloads at a time, the original images, and the best MP4 videos
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many pins are downloaded at the same time, at least 1
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the preferred image size, smaller sizes are tried when it's not available
+    pub fn with_image_size(mut self, image_size: ImageSize) -> Self {
+        self.image_size = image_size;
+        self
+    }
+
+    /// Sets how the video variant of the video pins is picked
+    pub fn with_video_preference(mut self, video_preference: VideoPreference) -> Self {
+        self.video_preference = video_preference;
+        self
+    }
+
+    /// Sets whether the videos of the video pins are downloaded, only their cover image is downloaded otherwise
+    pub fn with_videos(mut self, videos: bool) -> Self {
+        self.videos = videos;
+        self
+    }
+}
+
+/// The `manifest.json` of a mirrored board, it's what makes the next runs incremental
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The board, as of the last run
+    pub board: Board,
+    /// When the mirror was last updated, in seconds since the unix epoch
+    pub updated_at: u64,
+    /// The mirrored pins by id, including the ones removed from the board
+    pub pins: BTreeMap<String, MirroredPin>,
+}
+
+/// A pin of the manifest
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirroredPin {
+    /// The pin metadata, as of the last run that found it on the board
+    pub pin: Pin,
+    /// The section of the pin, `None` if it's directly on the board
+    pub section: Option<BoardSection>,
+    /// The downloaded files, relative to the mirror directory
+    pub files: Vec<PathBuf>,
+    /// When the pin was first found on the board, in seconds since the unix epoch
+    pub first_seen_at: u64,
+    /// When the media of the pin were downloaded, `None` until a download succeeds
+    pub downloaded_at: Option<u64>,
+    /// True if the pin is no longer on the board, its files are kept
+    pub removed: bool,
+    /// When the pin was found removed from the board
+    pub removed_at: Option<u64>,
+}
+
+/// The result of [`Pinterest::mirror_board`]
+#[derive(Debug)]
+pub struct MirrorReport {
+    /// The path of the written manifest
+    pub manifest_path: PathBuf,
+    /// The number of pins currently on the board
+    pub pins: usize,
+    /// The number of pins downloaded by this run
+    pub downloaded: usize,
+    /// The number of pins that were already downloaded
+    pub unchanged: usize,
+    /// The ids of the pins found removed from the board by this run
+    pub removed: Vec<String>,
+    /// The pins whose download failed, they are retried by the next run
+    pub failed: Vec<(String, PinterestError)>,
+}
+
+impl Manifest {
+    /// Reads the manifest of a mirror, returns `None` if the file doesn't exist
+    ///
+    /// # Errors
+    /// * `IoError` - If the file can't be read
+    /// * `ResponseError` - If the file is not a valid manifest
+    pub fn load<P: AsRef<Path>>(path: P) -> crate::Result<Option<Self>> {
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the manifest through a temporary file, so an interrupted write never leaves a truncated manifest
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        let path = path.as_ref();
+        let part_path = path.with_extension("json.part");
+        std::fs::write(&part_path, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(part_path, path)?;
+        Ok(())
+    }
+
+    /// Writes the manifest like [`Manifest::save`], with the file api of the current runtime
+    async fn store(&self, path: &Path) -> crate::Result<()> {
+        fs::write_atomic(path, &serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// Updates the manifest with the pins currently on the board,
+    /// flags the pins that are no longer there, and returns their ids
+    fn update(&mut self, board: Board, listed: Vec<(Pin, Option<BoardSection>)>, now: u64) -> Vec<String> {
+        self.board = board;
+        self.updated_at = now;
+
+        let mut seen = std::collections::HashSet::with_capacity(listed.len());
+        for (pin, section) in listed {
+            seen.insert(pin.id.clone());
+            let entry = self.pins.entry(pin.id.clone()).or_insert_with(|| MirroredPin {
+                first_seen_at: now,
+                ..Default::default()
+            });
+            entry.pin = pin;
+            entry.section = section;
+            entry.removed = false;
+            entry.removed_at = None;
+        }
+
+        let mut removed = Vec::new();
+        for (id, entry) in self.pins.iter_mut().filter(|(id, entry)| !entry.removed && !seen.contains(*id)) {
+            entry.removed = true;
+            entry.removed_at = Some(now);
+            removed.push(id.clone());
+        }
+        removed
+    }
+
+    /// Returns the ids of the pins on the board that were never downloaded, or whose files are missing
+    fn pending(&self, dest_dir: &Path) -> Vec<String> {
+        self.pins.iter()
+            .filter(|(_, entry)| !entry.removed)
+            .filter(|(_, entry)| entry.downloaded_at.is_none()
+                || entry.files.iter().any(|file| !dest_dir.join(file).exists()))
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+}
+
+impl Pinterest {
+    /// Mirrors a board into a directory: the media of every pin, and a `manifest.json` of the pins metadata
+    ///
+    /// The pins directly on the board are saved in the directory, and the pins of a section in a sub-directory
+    /// named after the section. The runs are incremental: only the pins that were not downloaded yet are fetched,
+    /// and the pins that are no longer on the board are flagged as `removed` in the manifest (their files are kept).
+    /// A pin that fails to download is reported and retried by the next run, it doesn't stop the mirror.
+    /// The manifest is saved as the downloads complete, so an interrupted run doesn't download the same pins again.
+    ///
+    /// # Arguments
+    /// * `board` - The board path like `username/board-name`, or its url
+    /// * `dest_dir` - The mirror directory, it's created if it doesn't exist
+    /// * `options` - The mirror options, see [`MirrorOptions`]
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use pinterest_rs::Pinterest;
+    /// use pinterest_rs::mirror::MirrorOptions;
+    ///
+    /// # async fn mirror(pinterest: &Pinterest) -> pinterest_rs::Result<()> {
+    /// let report = pinterest.mirror_board("username/board-name", "backup/board-name", &MirrorOptions::new()).await?;
+    /// println!("{} new pins, {} removed, {} failed", report.downloaded, report.removed.len(), report.failed.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// * `InvalidBoard` - If the board is not a `username/board-name` path or url
+    /// * `NotFound` - If the board doesn't exist or is not visible to this account
+    /// * `IoError` - If the directory or the manifest can't be written
+    pub async fn mirror_board<P: AsRef<Path>>(&self, board: &str, dest_dir: P, options: &MirrorOptions) -> crate::Result<MirrorReport> {
+        let dest_dir = dest_dir.as_ref();
+        let board = self.board(board).await?;
+
+        #[cfg(feature = "debug")]
+        info!("Listing the pins of the board {}", board.url);
+        let mut listed: Vec<(Pin, Option<BoardSection>)> = self.board_pins(&board).await?
+            .into_iter()
+            .map(|pin| (pin, None))
+            .collect();
+        for section in self.board_sections(&board).await? {
+            let pins = self.section_pins(&board, &section).await?;
+            listed.extend(pins.into_iter().map(|pin| (pin, Some(section.clone()))));
+        }
+        let pins = listed.len();
+
+        fs::create_dir_all(dest_dir).await?;
+        let manifest_path = dest_dir.join(MANIFEST_FILE_NAME);
+        let mut manifest = Manifest::load(&manifest_path)?.unwrap_or_default();
+        let now = unix_now();
+        let removed = manifest.update(board, listed, now);
+        let pending = manifest.pending(dest_dir);
+        #[cfg(feature = "debug")]
+        info!("{} pins on the board, {} to download, {} removed", pins, pending.len(), removed.len());
+        manifest.store(&manifest_path).await?;
+
+        let jobs: Vec<(String, Pin, PathBuf)> = pending.into_iter()
+            .map(|id| {
+                let entry = &manifest.pins[&id];
+                let dir = match &entry.section {
+                    Some(section) => dest_dir.join(section_dir_name(section)),
+                    None => dest_dir.to_path_buf(),
+                };
+                (id, entry.pin.clone(), dir)
+            })
+            .collect();
+        let mut results = futures::stream::iter(jobs)
+            .map(|(id, pin, dir)| async move {
+                let result = self.mirror_pin(&pin, &dir, options).await;
+                (id, result)
+            })
+            .buffer_unordered(options.concurrency);
+
+        let mut downloaded = 0;
+        let mut failed = Vec::new();
+        while let Some((id, result)) = results.next().await {
+            match result {
+                Ok(files) => {
+                    let entry = manifest.pins.get_mut(&id).expect("the pending pins are in the manifest");
+                    entry.files = files.into_iter()
+                        .map(|file| file.strip_prefix(dest_dir).map(Path::to_path_buf).unwrap_or(file))
+                        .collect();
+                    entry.downloaded_at = Some(now);
+                    downloaded += 1;
+                    if downloaded % MANIFEST_SAVE_INTERVAL == 0 {
+                        #[cfg(feature = "debug")]
+                        debug!("Saving the manifest after {} downloads", downloaded);
+                        manifest.store(&manifest_path).await?;
+                    }
+                }
+                Err(e) => {
+                    #[cfg(feature = "debug")]
+                    warn!("Couldn't download the pin {}: {}", id, e);
+                    failed.push((id, e));
+                }
+            }
+        }
+
+        manifest.store(&manifest_path).await?;
+
+        Ok(MirrorReport {
+            manifest_path,
+            pins,
+            downloaded,
+            unchanged: pins.saturating_sub(downloaded + failed.len()),
+            removed,
+            failed,
+        })
+    }
+
+    /// Downloads the image of a pin, and its video if it's a video pin, returns the written files
+    async fn mirror_pin(&self, pin: &Pin, dir: &Path, options: &MirrorOptions) -> crate::Result<Vec<PathBuf>> {
+        #[cfg(feature = "debug")]
+        debug!("Mirroring the pin {} into {:?}", pin.id, dir);
+        fs::create_dir_all(dir).await?;
+        let mut files = Vec::new();
+
+        if let Some(image_url) = pin.image_url() {
+            let download = self.download_image(image_url, options.image_size, dir.join(&pin.id)).await?;
+            files.extend(download.path);
+        }
+
+        let variant = options.videos
+            .then(|| self.resolve_best_video(pin, options.video_preference))
+            .flatten();
+        if let Some(variant) = variant {
+            let extension = match variant.format() {
+                VideoFormat::Hls => "ts",
+                VideoFormat::Mp4 => "mp4",
+            };
+            let path = dir.join(format!("{}.{extension}", pin.id));
+            self.download_video(&variant, &path).await?;
+            files.push(path);
+        }
+
+        Ok(files)
+    }
+}
+
+/// Returns a directory name for a section, keeping only the safe characters of its slug
+fn section_dir_name(section: &BoardSection) -> String {
+    let name = if section.slug.is_empty() { &section.id } else { &section.slug };
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[inline(always)]
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(id: &str) -> Pin {
+        Pin { id: id.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn update_adds_the_new_pins_and_flags_the_removed_ones() {
+        let mut manifest = Manifest::default();
+        let removed = manifest.update(Board::default(), vec![(pin("1"), None), (pin("2"), None)], 100);
+        assert!(removed.is_empty());
+        assert_eq!(manifest.pins.len(), 2);
+        assert_eq!(manifest.pins["1"].first_seen_at, 100);
+
+        let removed = manifest.update(Board::default(), vec![(pin("2"), None), (pin("3"), None)], 200);
+        assert_eq!(removed, ["1"]);
+        assert!(manifest.pins["1"].removed);
+        assert_eq!(manifest.pins["1"].removed_at, Some(200));
+        assert_eq!(manifest.pins["2"].first_seen_at, 100);
+        assert_eq!(manifest.pins["3"].first_seen_at, 200);
+        assert_eq!(manifest.updated_at, 200);
+
+        // A removed pin is only reported once, and is restored if it comes back
+        let removed = manifest.update(Board::default(), vec![(pin("1"), None), (pin("2"), None)], 300);
+        assert_eq!(removed, ["3"]);
+        assert!(!manifest.pins["1"].removed);
+        assert_eq!(manifest.pins["1"].removed_at, None);
+    }
+
+    #[test]
+    fn pending_skips_the_downloaded_and_removed_pins() {
+        let dest_dir = std::env::temp_dir().join(format!("pinterest_rs_mirror_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dest_dir).unwrap();
+        std::fs::write(dest_dir.join("1.jpg"), b"image").unwrap();
+
+        let mut manifest = Manifest::default();
+        manifest.update(Board::default(), vec![(pin("1"), None), (pin("2"), None), (pin("3"), None), (pin("4"), None)], 100);
+        let downloaded = |manifest: &mut Manifest, id: &str, file: &str| {
+            let entry = manifest.pins.get_mut(id).unwrap();
+            entry.files = vec![PathBuf::from(file)];
+            entry.downloaded_at = Some(100);
+        };
+        downloaded(&mut manifest, "1", "1.jpg");
+        // The file of the second pin was deleted from the mirror
+        downloaded(&mut manifest, "2", "2.jpg");
+        manifest.update(Board::default(), vec![(pin("1"), None), (pin("2"), None), (pin("3"), None)], 200);
+
+        assert_eq!(manifest.pending(&dest_dir), ["2", "3"]);
+        std::fs::remove_dir_all(dest_dir).unwrap();
+    }
+
+    #[test]
+    fn section_dir_name_keeps_the_safe_characters() {
+        let section = BoardSection { id: "42".to_string(), slug: "summer/../trips 2023".to_string(), ..Default::default() };
+        assert_eq!(section_dir_name(&section), "summer____trips_2023");
+        assert_eq!(section_dir_name(&BoardSection { id: "42".to_string(), ..Default::default() }), "42");
+    }
+
+    #[test]
+    fn the_manifest_round_trips() {
+        let mut manifest = Manifest::default();
+        manifest.update(Board { id: "7".to_string(), ..Default::default() }, vec![(pin("1"), None)], 100);
+        let path = std::env::temp_dir().join(format!("pinterest_rs_manifest_test_{}.json", std::process::id()));
+
+        manifest.save(&path).unwrap();
+        assert_eq!(Manifest::load(&path).unwrap(), Some(manifest));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(Manifest::load(&path).unwrap(), None);
+    }
+
+    async fn the_stored_manifest_round_trips(name: &str) {
+        let mut manifest = Manifest::default();
+        manifest.update(Board { id: "7".to_string(), ..Default::default() }, vec![(pin("1"), None)], 100);
+        let dir = std::env::temp_dir().join(format!("pinterest_rs_manifest_store_test_{name}_{}", std::process::id()));
+        let path = dir.join("nested").join(MANIFEST_FILE_NAME);
+
+        fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+        manifest.store(&path).await.unwrap();
+        assert_eq!(Manifest::load(&path).unwrap(), Some(manifest));
+        assert!(!path.with_extension("json.part").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[async_std::test]
+    async fn the_stored_manifest_round_trips_on_async_std() {
+        the_stored_manifest_round_trips("async_std").await;
+    }
+
+    #[tokio::test]
+    async fn the_stored_manifest_round_trips_on_tokio() {
+        the_stored_manifest_round_trips("tokio").await;
+    }
+}
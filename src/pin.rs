@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::json;
+
+use crate::Pinterest;
+use crate::board::{is_pin, PAGE_SIZE};
+use crate::video::{VideoList, VideoVariant};
+
+/// A pinterest pin, with the fields returned by the board feeds
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pin {
+    /// The pin id
+    pub id: String,
+    /// The pin title, if any
+    #[serde(default)]
+    pub title: Option<String>,
+    /// The pin description, if any
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The link of the pin, the page the image was saved from
+    #[serde(default)]
+    pub link: Option<String>,
+    /// The creation date, like `Mon, 02 Jan 2023 10:00:00 +0000`
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// The dominant color of the image, like `#a3b1c2`
+    #[serde(default)]
+    pub dominant_color: Option<String>,
+    /// The images of the pin by size, like `orig` or `736x`
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub images: HashMap<String, PinImage>,
+    /// The videos of the pin, if it's a video pin
+    #[serde(default)]
+    pub videos: Option<PinVideos>,
+}
+
+/// An image of a pin, at a given size
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinImage {
+    /// The pinimg url of the image
+    pub url: String,
+    /// The image width in pixels
+    #[serde(default)]
+    pub width: u32,
+    /// The image height in pixels
+    #[serde(default)]
+    pub height: u32,
+}
+
+/// The videos of a video pin
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinVideos {
+    /// The video variants
+    #[serde(default)]
+    pub video_list: VideoList,
+}
+
+impl Pin {
+    /// Returns the url of the largest image of the pin, the original one when it's available
+    pub fn image_url(&self) -> Option<&str> {
+        self.images.get("orig")
+            .or_else(|| self.images.values().max_by_key(|image| image.width as u64 * image.height as u64))
+            .map(|image| image.url.as_str())
+    }
+
+    /// Returns the video variants of the pin, if it's a video pin
+    pub fn video_list(&self) -> Option<&VideoList> {
+        self.videos.as_ref()
+            .map(|videos| &videos.video_list)
+            .filter(|video_list| !video_list.is_empty())
+    }
+
+    /// Returns the video variants of the pin, like `V_HLSV4` or `V_720P`, sorted by name,
+    /// empty if it's not a video pin, see [`Pinterest::resolve_best_video`] to pick one
+    pub fn video_variants(&self) -> Vec<VideoVariant> {
+        self.video_list().map(VideoList::variants).unwrap_or_default()
+    }
+}
+
+impl Pinterest {
+    /// Gets a pin from its id
+    ///
+    /// # Errors
+    /// * `NotFound` - If the pin doesn't exist or is not visible to this account
+    pub async fn pin(&self, id: &str) -> crate::Result<Pin> {
+        self.get_resource_data("PinResource", &format!("/pin/{id}/"), json!({
+            "id": id,
+            "field_set_key": "detailed",
+        })).await
+    }
+
+    /// Searches the pins, and returns up to `limit` results
+    ///
+    /// # Arguments
+    /// * `query` - The search query
+    /// * `limit` - The maximum number of pins to return, the search results never end
+    pub async fn search(&self, query: &str, limit: usize) -> crate::Result<Vec<Pin>> {
+        let source_url = format!("/search/pins/?q={}&rs=typed", urlencoding::encode(query));
+        self.get_resource_list("BaseSearchResource", &source_url, json!({
+            "query": query,
+            "scope": "pins",
+            "rs": "typed",
+            "page_size": PAGE_SIZE,
+        }), Some(limit), is_pin).await
+    }
+}
+
+/// Pinterest sends `null` instead of an empty object for some fields
+fn null_as_default<'de, D: Deserializer<'de>, T: Default + Deserialize<'de>>(deserializer: D) -> Result<T, D::Error> {
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
@@ -0,0 +1,217 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "debug")]
+use log::*;
+
+use crate::{Pinterest, PinterestError};
+use crate::credentials::CredentialStore;
+use crate::rate_limit::{EndpointClass, RateLimiter};
+
+/// A pool of authenticated accounts, that dispatches the read jobs across them
+///
+/// Every account is a separate [`Pinterest`] client, with its own cookie jar and its own rate limiter.
+/// An account is taken out of the rotation when its session cookies expire, or when a job fails with
+/// `Unauthorized`, and the job is retried with the next account.
+/// The jobs go to the accounts whose rate budget can serve them right away first.
+///
+/// # Example
+/// ```no_run
+/// use pinterest_rs::pool::PinterestPool;
+/// use pinterest_rs::rate_limit::RateLimiter;
+///
+/// # async fn run() -> pinterest_rs::Result<()> {
+/// let mut pool = PinterestPool::new();
+/// pool.load_account("first_account", "first_account.json", Some(RateLimiter::default()))?;
+/// pool.load_account("second_account", "second_account.json", Some(RateLimiter::default()))?;
+///
+/// let username = pool.run(|pinterest| async move {
+///     // Use the client of the account
+///     Ok(pinterest.username().to_string())
+/// }).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct PinterestPool {
+    accounts: Vec<PoolAccount>,
+    next: AtomicUsize,
+}
+
+struct PoolAccount {
+    client: Arc<Pinterest>,
+    expired: AtomicBool,
+}
+
+/// The state of an account of the pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountStatus {
+    /// The username of the account
+    pub username: String,
+    /// True if the session of the account expired, and it was taken out of the rotation
+    pub expired: bool,
+}
+
+impl PinterestPool {
+    /// Creates a new empty pool
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an authenticated client to the pool
+    pub fn add_account(&mut self, pinterest: Pinterest) {
+        #[cfg(feature = "debug")]
+        info!("Adding the account {} to the pool", pinterest.username());
+
+        self.accounts.push(PoolAccount {
+            client: Arc::new(pinterest),
+            expired: AtomicBool::new(false),
+        });
+    }
+
+    /// Creates a client from a session saved by the login methods, and adds it to the pool
+    ///
+    /// # Arguments
+    /// * `username` - The username of the account
+    /// * `cred_store` - The credentials store of the account, like the path of its credentials file
+    /// * `rate_limiter` - The rate budget of the account, if any
+    ///
+    /// # Errors
+    /// See [`Pinterest::load_session`]
+    pub fn load_account<C: CredentialStore>(&mut self, username: &str, cred_store: C, rate_limiter: Option<RateLimiter>) -> crate::Result<()> {
+        let mut pinterest = Pinterest::with_username(username);
+        pinterest.load_session(cred_store)?;
+        if let Some(rate_limiter) = rate_limiter {
+            pinterest = pinterest.with_rate_limiter(rate_limiter);
+        }
+
+        self.add_account(pinterest);
+        Ok(())
+    }
+
+    /// Returns the status of every account
+    pub fn accounts(&self) -> Vec<AccountStatus> {
+        self.accounts.iter()
+            .map(|account| AccountStatus {
+                username: account.client.username().to_string(),
+                expired: account.expired.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    /// Returns the number of accounts that are still in the rotation
+    pub fn available(&self) -> usize {
+        self.accounts.iter().filter(|account| !account.expired.load(Ordering::Relaxed)).count()
+    }
+
+    /// Replaces the client of an account (after logging in again for example), and puts it back in the rotation
+    ///
+    /// Returns false if there is no account with the same username in the pool
+    pub fn replace_account(&mut self, pinterest: Pinterest) -> bool {
+        let Some(account) = self.accounts.iter_mut()
+            .find(|account| account.client.username() == pinterest.username()) else {
+            return false;
+        };
+
+        account.client = Arc::new(pinterest);
+        account.expired.store(false, Ordering::Relaxed);
+        true
+    }
+
+    /// Picks the next account that is still in the rotation, preferring the ones whose rate budget can serve now
+    ///
+    /// The accounts whose session cookies expired are taken out of the rotation before dispatching to them.
+    /// When every account has to wait for its rate limiter, the next one in the rotation is picked and waits.
+    fn pick(&self) -> Option<&PoolAccount> {
+        let len = self.accounts.len();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let mut in_rotation = (0..len).map(|offset| &self.accounts[(start + offset) % len])
+            .filter(|account| !account.expired.load(Ordering::Relaxed))
+            .filter(|account| {
+                if !account.client.is_session_expired() {
+                    return true;
+                }
+                #[cfg(feature = "debug")]
+                warn!("The session cookies of {} expired, taking it out of the rotation", account.client.username());
+                account.expired.store(true, Ordering::Relaxed);
+                false
+            })
+            .peekable();
+
+        let first = *in_rotation.peek()?;
+        Some(in_rotation.find(|account| account.client.can_send_now(EndpointClass::Read)).unwrap_or(first))
+    }
+
+    /// Runs a job with the next available account
+    ///
+    /// If the job fails with `Unauthorized`, the account is taken out of the rotation and the job is retried with the next one
+    ///
+    /// # Errors
+    /// * `NoAccountAvailable` - If the pool is empty or all the sessions expired
+    /// * Any error returned by the job
+    pub async fn run<F, Fut, T>(&self, job: F) -> crate::Result<T>
+        where F: Fn(Arc<Pinterest>) -> Fut, Fut: Future<Output = crate::Result<T>> {
+        while let Some(account) = self.pick() {
+            match job(account.client.clone()).await {
+                Err(PinterestError::Unauthorized(_error)) => {
+                    #[cfg(feature = "debug")]
+                    warn!("The session of {} expired, taking it out of the rotation: {}", account.client.username(), _error);
+                    account.expired.store(true, Ordering::Relaxed);
+                }
+                result => return result,
+            }
+        }
+
+        Err(PinterestError::NoAccountAvailable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pinterest_login::cookie::SessionCookie;
+
+    use super::*;
+    use crate::clock::{Clock, MockClock};
+
+    /// A logged in client, whose session cookie expires at the given unix time
+    fn account(username: &str, expires: f64, clock: &MockClock) -> Pinterest {
+        let session = SessionCookie { expires: Some(expires), ..SessionCookie::new("_pinterest_sess", "session") };
+        let mut pinterest = Pinterest::with_username(username)
+            .with_rate_limiter(RateLimiter::new(1, 0.01, 1, 0.01).unwrap())
+            .with_clock(clock.clone());
+        pinterest.set_session_cookies(vec![session], "csrftoken".to_string()).unwrap();
+        pinterest
+    }
+
+    #[test]
+    fn pick_skips_the_accounts_with_expired_session_cookies() {
+        let clock = MockClock::new();
+        let mut pool = PinterestPool::new();
+        pool.add_account(account("expired", 1.0, &clock));
+        pool.add_account(account("valid", f64::from(u32::MAX), &clock));
+
+        assert_eq!(pool.pick().unwrap().client.username(), "valid");
+        assert_eq!(pool.pick().unwrap().client.username(), "valid");
+        assert_eq!(pool.available(), 1);
+    }
+
+    #[test]
+    fn pick_prefers_the_accounts_whose_rate_budget_can_serve_now() {
+        let clock = MockClock::new();
+        let mut pool = PinterestPool::new();
+        pool.add_account(account("first", f64::from(u32::MAX), &clock));
+        pool.add_account(account("second", f64::from(u32::MAX), &clock));
+
+        // The first account used its only token
+        let first = &pool.accounts[0].client;
+        assert!(first.rate_limiter.as_ref().unwrap().bucket(EndpointClass::Read).try_acquire(clock.now()).is_ok());
+        assert_eq!(pool.pick().unwrap().client.username(), "second");
+        assert_eq!(pool.pick().unwrap().client.username(), "second");
+
+        // When no account can serve now, the rotation goes on
+        let second = &pool.accounts[1].client;
+        assert!(second.rate_limiter.as_ref().unwrap().bucket(EndpointClass::Read).try_acquire(clock.now()).is_ok());
+        assert_eq!(pool.pick().unwrap().client.username(), "first");
+        assert_eq!(pool.pick().unwrap().client.username(), "second");
+    }
+}
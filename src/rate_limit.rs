@@ -0,0 +1,228 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use reqwest::Method;
+#[cfg(feature = "debug")]
+use log::*;
+
+use crate::clock::Clock;
+use crate::PinterestError;
+
+/// The class of an endpoint, reads and writes have separate rate limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointClass {
+    /// `GET` and `HEAD` requests
+    Read,
+    /// Every other request (`POST`, `PUT`, `DELETE`, ...)
+    Write,
+}
+
+impl EndpointClass {
+    /// Returns the class of a request method
+    pub fn from_method(method: &Method) -> Self {
+        if method == Method::GET || method == Method::HEAD {
+            EndpointClass::Read
+        } else {
+            EndpointClass::Write
+        }
+    }
+}
+
+/// A token bucket, that allows bursts of `capacity` requests, and refills `refill_per_sec` tokens per second
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Option<Instant>,
+}
+
+impl TokenBucket {
+    /// Creates a new full token bucket
+    ///
+    /// # Arguments
+    /// * `capacity` - The maximum number of requests that can be sent in a burst (at least 1)
+    /// * `refill_per_sec` - The sustained number of requests per second
+    ///
+    /// # Errors
+    /// * `InvalidRateLimit` - If `refill_per_sec` is not a positive finite number, the bucket would never refill
+    pub fn new(capacity: u32, refill_per_sec: f64) -> crate::Result<Self> {
+        if !refill_per_sec.is_finite() || refill_per_sec <= 0.0 {
+            return Err(PinterestError::InvalidRateLimit(refill_per_sec));
+        }
+
+        let capacity = capacity.max(1) as f64;
+        Ok(Self {
+            capacity,
+            refill_per_sec,
+            state: Mutex::new(BucketState { tokens: capacity, last_refill: None }),
+        })
+    }
+
+    /// Returns true if a token is available, without taking it
+    pub fn has_token(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut state, now);
+        state.tokens >= 1.0
+    }
+
+    /// Takes a token if one is available, otherwise returns how long to wait for the next one
+    pub fn try_acquire(&self, now: Instant) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut state, now);
+
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - state.tokens) / self.refill_per_sec))
+        }
+    }
+
+    #[inline(always)]
+    fn refill(&self, state: &mut BucketState, now: Instant) {
+        if let Some(last_refill) = state.last_refill {
+            let elapsed = now.saturating_duration_since(last_refill).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        }
+        state.last_refill = Some(now);
+    }
+
+    /// Waits until a token is available and takes it
+    pub async fn acquire(&self, clock: &dyn Clock) {
+        while let Err(wait) = self.try_acquire(clock.now()) {
+            #[cfg(feature = "debug")]
+            debug!("The rate limit is reached, waiting {:?}", wait);
+            clock.sleep(wait).await;
+        }
+    }
+}
+
+/// A client side rate limiter, with a token bucket for the read endpoints and another one for the write endpoints
+///
+/// # Example
+/// ```
+/// use pinterest_rs::rate_limit::RateLimiter;
+///
+/// // Bursts of 10 reads at 2 reads per second, and bursts of 2 writes at 1 write every 2 seconds
+/// let rate_limiter = RateLimiter::new(10, 2.0, 2, 0.5)?;
+/// # Ok::<(), pinterest_rs::PinterestError>(())
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    read: TokenBucket,
+    write: TokenBucket,
+}
+
+impl RateLimiter {
+    /// Creates a new rate limiter
+    ///
+    /// # Arguments
+    /// * `read_burst` - The maximum number of read requests in a burst
+    /// * `reads_per_sec` - The sustained number of read requests per second
+    /// * `write_burst` - The maximum number of write requests in a burst
+    /// * `writes_per_sec` - The sustained number of write requests per second
+    ///
+    /// # Errors
+    /// * `InvalidRateLimit` - If a rate is not a positive finite number
+    pub fn new(read_burst: u32, reads_per_sec: f64, write_burst: u32, writes_per_sec: f64) -> crate::Result<Self> {
+        Ok(Self {
+            read: TokenBucket::new(read_burst, reads_per_sec)?,
+            write: TokenBucket::new(write_burst, writes_per_sec)?,
+        })
+    }
+
+    /// Returns the token bucket of an endpoint class
+    pub fn bucket(&self, class: EndpointClass) -> &TokenBucket {
+        match class {
+            EndpointClass::Read => &self.read,
+            EndpointClass::Write => &self.write,
+        }
+    }
+
+    /// Waits until a request of the given class is allowed
+    #[inline]
+    pub async fn acquire(&self, class: EndpointClass, clock: &dyn Clock) {
+        self.bucket(class).acquire(clock).await
+    }
+}
+
+impl Default for RateLimiter {
+    /// Creates a new rate limiter, with the following limits:
+    /// * reads - bursts of 10, 2 per second
+    /// * writes - bursts of 3, 1 every 2 seconds
+    fn default() -> Self {
+        Self::new(10, 2.0, 3, 0.5).expect("the default rates are positive")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    #[test]
+    fn new_rejects_the_rates_that_never_refill() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(TokenBucket::new(1, rate), Err(PinterestError::InvalidRateLimit(_))));
+        }
+        assert!(RateLimiter::new(10, 2.0, 3, 0.0).is_err());
+    }
+
+    #[test]
+    fn try_acquire_allows_a_burst_then_waits_for_the_refill() {
+        let bucket = TokenBucket::new(3, 2.0).unwrap();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(bucket.try_acquire(now), Ok(()));
+        }
+        assert_eq!(bucket.try_acquire(now), Err(Duration::from_millis(500)));
+
+        // Half a token was refilled, half a token is missing
+        let later = now + Duration::from_millis(250);
+        assert_eq!(bucket.try_acquire(later), Err(Duration::from_millis(250)));
+        assert_eq!(bucket.try_acquire(later + Duration::from_millis(250)), Ok(()));
+    }
+
+    #[test]
+    fn try_acquire_refills_up_to_the_capacity() {
+        let bucket = TokenBucket::new(2, 1.0).unwrap();
+        let now = Instant::now();
+        assert_eq!(bucket.try_acquire(now), Ok(()));
+        assert_eq!(bucket.try_acquire(now), Ok(()));
+
+        let much_later = now + Duration::from_secs(60);
+        assert_eq!(bucket.try_acquire(much_later), Ok(()));
+        assert_eq!(bucket.try_acquire(much_later), Ok(()));
+        assert!(bucket.try_acquire(much_later).is_err());
+    }
+
+    async fn acquire_waits_with_the_clock() {
+        let clock = MockClock::new();
+        let rate_limiter = RateLimiter::new(2, 4.0, 1, 1.0).unwrap();
+
+        for _ in 0..4 {
+            rate_limiter.acquire(EndpointClass::Read, &clock).await;
+        }
+        rate_limiter.acquire(EndpointClass::Write, &clock).await;
+
+        // The burst of 2 reads was free, the 2 next reads waited a quarter of a second each,
+        // and the write bucket is independent
+        assert_eq!(clock.sleeps(), [Duration::from_millis(250), Duration::from_millis(250)]);
+    }
+
+    #[async_std::test]
+    async fn acquire_waits_with_the_clock_on_async_std() {
+        acquire_waits_with_the_clock().await;
+    }
+
+    #[tokio::test]
+    async fn acquire_waits_with_the_clock_on_tokio() {
+        acquire_waits_with_the_clock().await;
+    }
+}
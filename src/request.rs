@@ -0,0 +1,238 @@
+use reqwest::{Request, RequestBuilder, Response};
+#[cfg(feature = "debug")]
+use log::*;
+
+use crate::{Pinterest, PinterestError};
+use crate::error::check_response;
+use crate::proxy::ProxyOutcome;
+use crate::rate_limit::EndpointClass;
+
+impl Pinterest {
+    /// Sends a request through the rate limiter and the proxy pool, and retries it according to the retry policy
+    ///
+    /// Returns the response if its status is a success, or the parsed api error otherwise,
+    /// `RetriesExhausted` is returned when the retry policy gives up on a retryable error after retrying at least once,
+    /// the errors the policy doesn't retry are returned as they are, even after some retries.
+    /// A request that fails because of its proxy is sent again through the next available proxy.
+    pub(crate) async fn send(&self, request: RequestBuilder) -> crate::Result<Response> {
+        let request = request.build()?;
+        let class = EndpointClass::from_method(request.method());
+        let mut attempt = 0;
+        let mut proxy_failures = 0;
+
+        loop {
+            // Streaming bodies can't be cloned, so they are sent only once
+            let Some(current) = request.try_clone() else {
+                self.wait_for_rate_limit(class).await;
+                return self.execute(request).await.0;
+            };
+
+            self.wait_for_rate_limit(class).await;
+            let error = match self.execute(current).await {
+                (Ok(response), _) => return Ok(response),
+                (Err(_error), true) if proxy_failures < self.proxy_pool.as_ref().map_or(0, |pool| pool.entries.len()) => {
+                    #[cfg(feature = "debug")]
+                    info!("The request failed because of its proxy ({}), trying the next one", _error);
+                    proxy_failures += 1;
+                    continue;
+                }
+                (Err(error), _) => error,
+            };
+
+            let Some(retry_policy) = &self.retry_policy else {
+                return Err(error);
+            };
+            let Some(delay) = retry_policy.retry_delay(attempt, &error) else {
+                return Err(if attempt > 0 && retry_policy.should_retry(&error) {
+                    PinterestError::RetriesExhausted { attempts: attempt + 1, last_error: Box::new(error) }
+                } else {
+                    error
+                });
+            };
+
+            #[cfg(feature = "debug")]
+            info!("The request failed ({}), retrying in {:?}", error, delay);
+
+            self.clock.sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Executes a single attempt with the client of the next proxy,
+    /// returns true with the result if the attempt failed because of the proxy
+    async fn execute(&self, request: Request) -> (crate::Result<Response>, bool) {
+        let Some(proxy_pool) = &self.proxy_pool else {
+            return (self.execute_with(&self.client, request).await, false);
+        };
+
+        let Some(index) = proxy_pool.pick() else {
+            return (Err(PinterestError::NoProxyAvailable), false);
+        };
+        let client = proxy_pool.entries[index].client.as_ref().unwrap_or(&self.client);
+
+        let started = self.clock.now();
+        let result = self.execute_with(client, request).await;
+        let latency = self.clock.now().saturating_duration_since(started);
+
+        let outcome = match &result {
+            Err(PinterestError::RequestError(e)) if e.is_connect() || e.is_timeout() => ProxyOutcome::Failure(e.to_string()),
+            // The proxy is blocked, or it refused the request
+            Err(e @ PinterestError::Forbidden(_)) => ProxyOutcome::Failure(e.to_string()),
+            Err(e @ PinterestError::ApiError(error)) if error.http_status == 407 => ProxyOutcome::Failure(e.to_string()),
+            Err(e @ PinterestError::RateLimited { .. }) => ProxyOutcome::RateLimited {
+                account: self.username.clone(),
+                error: e.to_string(),
+            },
+            _ => ProxyOutcome::Success,
+        };
+        let is_proxy_failure = proxy_pool.record(index, latency, outcome);
+
+        (result, is_proxy_failure)
+    }
+
+    #[inline(always)]
+    async fn execute_with(&self, client: &reqwest::Client, request: Request) -> crate::Result<Response> {
+        check_response(client.execute(request).await?).await
+    }
+
+    /// Returns true if a request of the given class can be sent now without waiting for the rate limiter
+    #[cfg(feature = "login")]
+    pub(crate) fn can_send_now(&self, class: EndpointClass) -> bool {
+        self.rate_limiter.as_ref()
+            .is_none_or(|rate_limiter| rate_limiter.bucket(class).has_token(self.clock.now()))
+    }
+
+    #[inline(always)]
+    async fn wait_for_rate_limit(&self, class: EndpointClass) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire(class, self.clock.as_ref()).await;
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use crate::clock::MockClock;
+    use crate::proxy::{ProxyPool, RotationStrategy};
+    use crate::retry::DefaultRetryPolicy;
+    use crate::{Pinterest, PinterestError};
+
+    const RATE_LIMITED: &str = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 3600\r\n\
+        Content-Length: 0\r\nConnection: close\r\n\r\n";
+    const SERVICE_UNAVAILABLE: &str = "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    pub(crate) const FORBIDDEN: &str = "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    pub(crate) const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    /// Serves the given responses in order, one per request, returns the server url
+    pub(crate) fn server(responses: Vec<&'static str>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for (stream, response) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        url
+    }
+
+    fn client(clock: MockClock) -> Pinterest {
+        #[cfg(not(feature = "credentials"))]
+        let pinterest = Pinterest::new("username", None);
+        #[cfg(feature = "credentials")]
+        let pinterest = Pinterest::new("username", "csrftoken", None);
+
+        pinterest
+            .with_retry_policy(DefaultRetryPolicy::new(2, Duration::from_millis(100), Duration::from_secs(30)))
+            .with_clock(clock)
+    }
+
+    async fn send_gives_up_after_the_retries() {
+        let url = server(vec![RATE_LIMITED; 3]);
+        let clock = MockClock::new();
+        let pinterest = client(clock.clone());
+
+        let error = pinterest.send(pinterest.client.get(url)).await.unwrap_err();
+        assert!(matches!(error.last_error(), PinterestError::RateLimited { .. }));
+        match error {
+            PinterestError::RetriesExhausted { attempts, last_error } => {
+                assert_eq!(attempts, 3);
+                assert!(matches!(*last_error, PinterestError::RateLimited { retry_after: Some(_), .. }));
+            }
+            error => panic!("unexpected error: {error}"),
+        }
+        // The hour of Retry-After was capped by the max delay
+        assert_eq!(clock.sleeps(), [Duration::from_secs(30), Duration::from_secs(30)]);
+    }
+
+    async fn send_returns_the_errors_that_are_not_retried_as_they_are() {
+        let url = server(vec![SERVICE_UNAVAILABLE, NOT_FOUND]);
+        let clock = MockClock::new();
+        let pinterest = client(clock.clone());
+
+        let error = pinterest.send(pinterest.client.get(url)).await.unwrap_err();
+        assert!(matches!(error, PinterestError::NotFound(_)), "unexpected error: {error}");
+        assert_eq!(clock.sleeps().len(), 1);
+    }
+
+    async fn send_evicts_a_blocked_proxy_and_uses_the_next_one() {
+        // The servers act as the proxies, the first one is blocked
+        let proxy_pool = ProxyPool::new([server(vec![FORBIDDEN]), server(vec![OK])], RotationStrategy::Sticky).unwrap()
+            .with_eviction_threshold(1);
+        let clock = MockClock::new();
+        let pinterest = client(clock.clone()).with_proxy_pool(proxy_pool).unwrap();
+
+        let response = pinterest.send(pinterest.client.get("http://pinterest.invalid/")).await.unwrap();
+        assert!(response.status().is_success());
+        assert!(clock.sleeps().is_empty());
+
+        let stats = pinterest.proxy_pool().unwrap().stats();
+        assert_eq!((stats[0].1.failures, stats[0].1.evicted), (1, true));
+        assert!(stats[0].1.last_error.as_ref().unwrap().contains("403"));
+        assert_eq!((stats[1].1.successes, stats[1].1.evicted), (1, false));
+    }
+
+    #[async_std::test]
+    async fn send_gives_up_after_the_retries_on_async_std() {
+        send_gives_up_after_the_retries().await;
+    }
+
+    #[tokio::test]
+    async fn send_gives_up_after_the_retries_on_tokio() {
+        send_gives_up_after_the_retries().await;
+    }
+
+    #[async_std::test]
+    async fn send_returns_the_errors_that_are_not_retried_on_async_std() {
+        send_returns_the_errors_that_are_not_retried_as_they_are().await;
+    }
+
+    #[tokio::test]
+    async fn send_returns_the_errors_that_are_not_retried_on_tokio() {
+        send_returns_the_errors_that_are_not_retried_as_they_are().await;
+    }
+
+    #[async_std::test]
+    async fn send_evicts_a_blocked_proxy_on_async_std() {
+        send_evicts_a_blocked_proxy_and_uses_the_next_one().await;
+    }
+
+    #[tokio::test]
+    async fn send_evicts_a_blocked_proxy_on_tokio() {
+        send_evicts_a_blocked_proxy_and_uses_the_next_one().await;
+    }
+}
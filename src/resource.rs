@@ -0,0 +1,88 @@
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+#[cfg(feature = "debug")]
+use log::*;
+
+use crate::{Pinterest, PINTEREST_BASE_URL};
+
+/// The bookmark returned with the last page of a paginated resource
+const END_BOOKMARK: &str = "-end-";
+
+/// The resource body of a successful response, with the bookmark of the next page if any
+pub(crate) struct ResourcePage {
+    pub(crate) data: Value,
+    pub(crate) bookmark: Option<String>,
+}
+
+impl Pinterest {
+    /// Gets a pinterest web resource, like `BoardResource`, and returns its `resource_response`
+    ///
+    /// # Arguments
+    /// * `name` - The resource name, like `BoardResource`
+    /// * `source_url` - The path of the page that would load the resource, like `/username/board/`
+    /// * `options` - The options of the resource
+    pub(crate) async fn get_resource(&self, name: &str, source_url: &str, options: &Value) -> crate::Result<ResourcePage> {
+        let url = format!("{PINTEREST_BASE_URL}/resource/{name}/get/");
+        let data = json!({ "options": options, "context": {} }).to_string();
+        #[cfg(feature = "debug")]
+        debug!("Getting the resource {} with the options: {}", name, options);
+
+        let body = self.send(self.client.get(url)
+            .query(&[("source_url", source_url), ("data", data.as_str())])).await?
+            .bytes().await?;
+        let mut body: Value = serde_json::from_slice(&body)?;
+        let response = body["resource_response"].take();
+        let bookmark = response["bookmark"].as_str()
+            .or_else(|| body["resource"]["options"]["bookmarks"][0].as_str())
+            .filter(|bookmark| *bookmark != END_BOOKMARK)
+            .map(str::to_string);
+
+        Ok(ResourcePage { data: response["data"].clone(), bookmark })
+    }
+
+    /// Gets a single object resource, like a board or a pin
+    pub(crate) async fn get_resource_data<T: DeserializeOwned>(&self, name: &str, source_url: &str, options: Value) -> crate::Result<T> {
+        Ok(serde_json::from_value(self.get_resource(name, source_url, &options).await?.data)?)
+    }
+
+    /// Gets the pages of a paginated list resource, like the pins of a board, until the end or the limit
+    ///
+    /// The items for which `keep` returns false are skipped, the feeds mix the pins with stories and ads
+    pub(crate) async fn get_resource_list<T: DeserializeOwned>(&self, name: &str, source_url: &str, mut options: Value,
+                                                               limit: Option<usize>, keep: impl Fn(&Value) -> bool)
+                                                               -> crate::Result<Vec<T>> {
+        let mut items = Vec::new();
+        loop {
+            let page = self.get_resource(name, source_url, &options).await?;
+            // Some list resources wrap the list in a `results` object, like the search
+            let list = match page.data {
+                Value::Array(list) => list,
+                Value::Object(mut object) => match object.remove("results") {
+                    Some(Value::Array(list)) => list,
+                    _ => Vec::new(),
+                },
+                _ => Vec::new(),
+            };
+            // An empty page ends the list too, so a bookmark that never ends can't loop forever
+            let is_empty = list.is_empty();
+            for item in list.into_iter().filter(|item| keep(item)) {
+                if limit.is_some_and(|limit| items.len() >= limit) {
+                    return Ok(items);
+                }
+                items.push(serde_json::from_value(item)?);
+            }
+            if limit.is_some_and(|limit| items.len() >= limit) {
+                break;
+            }
+
+            let Some(bookmark) = page.bookmark.filter(|_| !is_empty) else {
+                break;
+            };
+            #[cfg(feature = "debug")]
+            trace!("Getting the next page of {} ({} items so far)", name, items.len());
+            options["bookmarks"] = json!([bookmark]);
+        }
+
+        Ok(items)
+    }
+}
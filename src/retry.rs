@@ -0,0 +1,163 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::PinterestError;
+
+/// The retry policy trait, that decides if a failed request should be retried and after how long
+/// You can implement this trait for your own struct, and set it with [`crate::Pinterest::with_retry_policy`]
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use pinterest_rs::PinterestError;
+/// use pinterest_rs::retry::RetryPolicy;
+///
+/// struct RetryOnceOnRateLimit;
+///
+/// impl RetryPolicy for RetryOnceOnRateLimit {
+///     fn retry_delay(&self, attempt: u32, error: &PinterestError) -> Option<Duration> {
+///         match error {
+///             PinterestError::RateLimited { retry_after, .. } if attempt == 0 => Some(retry_after.unwrap_or(Duration::from_secs(30))),
+///             _ => None,
+///         }
+///     }
+/// }
+/// ```
+pub trait RetryPolicy: Send + Sync {
+    /// Returns the delay before the next attempt, or `None` to give up
+    ///
+    /// # Arguments
+    /// * `attempt` - The number of retries done so far, starting from 0
+    /// * `error` - The error of the last attempt
+    fn retry_delay(&self, attempt: u32, error: &PinterestError) -> Option<Duration>;
+
+    /// Returns true if the error is one the policy retries, to tell apart the retries that ran out
+    /// from an error that was never retried, like a 404 after a 503
+    ///
+    /// The default implementation checks if the first attempt would be retried
+    fn should_retry(&self, error: &PinterestError) -> bool {
+        self.retry_delay(0, error).is_some()
+    }
+}
+
+/// The default retry policy, that retries rate limited (429) and server (5xx) errors
+/// with an exponential backoff and a full jitter, and honors the `Retry-After` header
+#[derive(Debug, Clone)]
+pub struct DefaultRetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl DefaultRetryPolicy {
+    /// Creates a new default retry policy
+    ///
+    /// # Arguments
+    /// * `max_retries` - The maximum number of retries, after the first attempt
+    /// * `base_delay` - The delay before the first retry, doubled for each following retry
+    /// * `max_delay` - The maximum delay between two attempts, the `Retry-After` header is also capped by it
+    pub fn new(max_retries: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            base_delay,
+            max_delay,
+        }
+    }
+
+    /// Returns true if the error is a 429 or a 5xx response
+    pub fn is_retryable(error: &PinterestError) -> bool {
+        match error {
+            PinterestError::RateLimited { .. } => true,
+            PinterestError::ApiError(error) => error.http_status >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl RetryPolicy for DefaultRetryPolicy {
+    #[inline]
+    fn should_retry(&self, error: &PinterestError) -> bool {
+        Self::is_retryable(error)
+    }
+
+    fn retry_delay(&self, attempt: u32, error: &PinterestError) -> Option<Duration> {
+        if attempt >= self.max_retries || !Self::is_retryable(error) {
+            return None;
+        }
+
+        if let PinterestError::RateLimited { retry_after: Some(retry_after), .. } = error {
+            return Some((*retry_after).min(self.max_delay));
+        }
+
+        let backoff = self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        // Full jitter, so the clients that failed together don't retry together
+        Some(backoff.mul_f64(random_fraction()))
+    }
+}
+
+impl Default for DefaultRetryPolicy {
+    /// Creates a new default retry policy, with the following values:
+    /// * `max_retries` - 5
+    /// * `base_delay` - 500 milliseconds
+    /// * `max_delay` - 60 seconds
+    fn default() -> Self {
+        Self::new(5, Duration::from_millis(500), Duration::from_secs(60))
+    }
+}
+
+/// Returns a random number in `[0, 1)`, good enough for jitter
+#[inline(always)]
+fn random_fraction() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApiError;
+
+    fn rate_limited(retry_after: Option<Duration>) -> PinterestError {
+        PinterestError::RateLimited {
+            retry_after,
+            error: Box::new(ApiError { http_status: 429, ..Default::default() }),
+        }
+    }
+
+    fn server_error(http_status: u16) -> PinterestError {
+        PinterestError::ApiError(Box::new(ApiError { http_status, ..Default::default() }))
+    }
+
+    #[test]
+    fn retry_after_is_honored_and_capped() {
+        let policy = DefaultRetryPolicy::new(3, Duration::from_millis(100), Duration::from_secs(10));
+        assert_eq!(policy.retry_delay(0, &rate_limited(Some(Duration::from_secs(2)))), Some(Duration::from_secs(2)));
+        assert_eq!(policy.retry_delay(0, &rate_limited(Some(Duration::from_secs(3600)))), Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn backoff_is_jittered_below_the_exponential_delay() {
+        let policy = DefaultRetryPolicy::new(10, Duration::from_millis(100), Duration::from_secs(1));
+        for attempt in 0..10 {
+            let delay = policy.retry_delay(attempt, &server_error(503)).unwrap();
+            let backoff = Duration::from_millis(100 * 2u64.pow(attempt)).min(Duration::from_secs(1));
+            assert!(delay <= backoff, "attempt {attempt}: {delay:?} > {backoff:?}");
+        }
+    }
+
+    #[test]
+    fn gives_up_after_the_max_retries_and_on_other_errors() {
+        let policy = DefaultRetryPolicy::new(2, Duration::from_millis(100), Duration::from_secs(1));
+        assert!(policy.retry_delay(1, &rate_limited(None)).is_some());
+        assert!(policy.retry_delay(2, &rate_limited(None)).is_none());
+        assert!(policy.retry_delay(0, &server_error(404)).is_none());
+        assert!(policy.retry_delay(0, &PinterestError::MissingCrfToken).is_none());
+
+        assert!(policy.should_retry(&rate_limited(None)));
+        assert!(policy.should_retry(&server_error(503)));
+        assert!(!policy.should_retry(&server_error(404)));
+    }
+}
@@ -0,0 +1,408 @@
+use std::path::Path;
+use pinterest_login::cookie::SessionCookie;
+use reqwest::cookie::CookieStore;
+use url::Url;
+#[cfg(feature = "debug")]
+use log::*;
+
+use crate::{Pinterest, PinterestError, PINTEREST_BASE_URL};
+use crate::login::crf_token;
+
+/// The formats the session can be exported to, see [`Pinterest::export_session`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionFormat {
+    /// A Netscape `cookies.txt` file, for curl (`-b cookies.txt`), wget, or python's `MozillaCookieJar`
+    CookiesTxt,
+    /// A json array of the cookies with all their attributes, the format of the credentials files
+    Json,
+    /// The value of a `Cookie` header, like `csrftoken=...; _pinterest_sess=...`
+    CookieHeader,
+}
+
+impl Pinterest {
+    /// Creates a client from the pinterest cookies of a Netscape `cookies.txt` file,
+    /// like the ones exported by the browser extensions or by `curl -c`
+    ///
+    /// # Arguments
+    /// * `username` - The username of the account
+    /// * `path` - The path of the `cookies.txt` file
+    ///
+    /// # Errors
+    /// * `InvalidCookies` - If a line of the file is malformed
+    /// * `MissingCrfToken` - If there is no `csrftoken` cookie for pinterest
+    pub fn from_cookies_txt<P: AsRef<Path>>(username: &str, path: P) -> crate::Result<Self> {
+        #[cfg(feature = "debug")]
+        info!("Loading the cookies from the cookies.txt file: {:?}", path.as_ref());
+
+        let cookies = parse_cookies_txt(&std::fs::read_to_string(path)?)?;
+        Self::from_cookies(username, cookies)
+    }
+
+    /// Creates a client from a `Cookie` header copied from the browser developer tools,
+    /// like `csrftoken=...; _pinterest_sess=...; _auth=1`
+    ///
+    /// # Arguments
+    /// * `username` - The username of the account
+    /// * `cookie_header` - The value of the `Cookie` header, with or without the `Cookie:` prefix
+    ///
+    /// # Errors
+    /// * `MissingCrfToken` - If there is no `csrftoken` cookie in the header
+    /// * `InvalidCookies` - If the `csrftoken` cookie is not a valid header value
+    pub fn from_cookie_header(username: &str, cookie_header: &str) -> crate::Result<Self> {
+        Self::from_cookies(username, parse_cookie_header(cookie_header))
+    }
+
+    /// Exports the current session, to hand it to other tools
+    ///
+    /// The cookie values are read from the live cookie jar, so the cookies refreshed by the responses are included,
+    /// the other attributes come from the cookies the session was created with
+    ///
+    /// # Errors
+    /// * `InvalidCookies` - If a cookie can't be written in the format, like a tab in a `cookies.txt` value
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use pinterest_rs::Pinterest;
+    /// use pinterest_rs::session::SessionFormat;
+    ///
+    /// # fn export(pinterest: &Pinterest) -> pinterest_rs::Result<()> {
+    /// std::fs::write("cookies.txt", pinterest.export_session(SessionFormat::CookiesTxt)?)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn export_session(&self, format: SessionFormat) -> crate::Result<String> {
+        let cookies = self.live_session_cookies();
+        #[cfg(feature = "debug")]
+        debug!("Exporting {} cookies as {:?}", cookies.len(), format);
+
+        Ok(match format {
+            SessionFormat::CookiesTxt => format_cookies_txt(&cookies)?,
+            SessionFormat::Json => serde_json::to_string_pretty(&cookies)?,
+            SessionFormat::CookieHeader => format_cookie_header(&cookies),
+        })
+    }
+
+    /// Returns the session cookies with the values currently in the cookie jar,
+    /// the expired cookies are dropped, and the cookies set by the responses are added
+    pub fn live_session_cookies(&self) -> Vec<SessionCookie> {
+        let url = Url::parse(PINTEREST_BASE_URL).unwrap();
+        let live: Vec<(String, String)> = self.cookie_jar.cookies(&url)
+            .and_then(|header| header.to_str().ok().map(str::to_string))
+            .map(|header| parse_cookie_header(&header).into_iter().map(|c| (c.name, c.value)).collect())
+            .unwrap_or_default();
+
+        let mut cookies: Vec<SessionCookie> = self.session.iter()
+            .filter(|cookie| !cookie.is_expired())
+            .cloned()
+            .map(|mut cookie| {
+                if let Some((_, value)) = live.iter().find(|(name, _)| *name == cookie.name) {
+                    cookie.value = value.clone();
+                }
+                cookie
+            })
+            .collect();
+
+        for (name, value) in live {
+            if !cookies.iter().any(|cookie| cookie.name == name) {
+                cookies.push(SessionCookie::new(name, value));
+            }
+        }
+
+        cookies
+    }
+
+    /// Creates a client from a list of session cookies
+    ///
+    /// # Errors
+    /// * `MissingCrfToken` - If there is no `csrftoken` cookie
+    pub fn from_cookies(username: &str, cookies: Vec<SessionCookie>) -> crate::Result<Self> {
+        #[cfg(feature = "debug")]
+        debug!("Creating a client from {} cookies", cookies.len());
+
+        let crf_token = crf_token(&cookies)?;
+        let mut pinterest = Self::with_username(username);
+        pinterest.set_session_cookies(cookies, crf_token)?;
+        Ok(pinterest)
+    }
+}
+
+/// Parses the pinterest cookies of a Netscape `cookies.txt` file, the cookies of the other sites are skipped
+///
+/// # Example
+/// ```
+/// use pinterest_rs::session::parse_cookies_txt;
+///
+/// let cookies = parse_cookies_txt("# Netscape HTTP Cookie File\n\
+///     .pinterest.com\tTRUE\t/\tTRUE\t1893456000\tcsrftoken\t1234\n\
+///     #HttpOnly_.pinterest.com\tTRUE\t/\tTRUE\t0\t_pinterest_sess\tabcd\n\
+///     .example.com\tTRUE\t/\tFALSE\t0\tother\tvalue\n")?;
+///
+/// assert_eq!(cookies.len(), 2);
+/// assert!(cookies[1].http_only);
+/// assert_eq!(cookies[1].expires, None);
+/// # Ok::<(), pinterest_rs::PinterestError>(())
+/// ```
+///
+/// # Errors
+/// * `InvalidCookies` - If a line of the file is malformed
+pub fn parse_cookies_txt(cookies_txt: &str) -> crate::Result<Vec<SessionCookie>> {
+    const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+    let mut cookies = Vec::new();
+    for (number, line) in cookies_txt.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(line) => (line, true),
+            None if line.trim().is_empty() || line.starts_with('#') => continue,
+            None => (line, false),
+        };
+
+        let invalid = || PinterestError::InvalidCookies(format!("line {}: {}", number + 1, line));
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [domain, include_subdomains, path, secure, expires, name, value] = fields[..] else {
+            return Err(invalid());
+        };
+
+        if !is_pinterest_domain(domain) {
+            continue;
+        }
+
+        let expires: i64 = expires.trim().parse().map_err(|_| invalid())?;
+        let domain = if include_subdomains.eq_ignore_ascii_case("TRUE") && !domain.starts_with('.') {
+            format!(".{domain}")
+        } else {
+            domain.to_string()
+        };
+
+        cookies.push(SessionCookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            domain,
+            path: path.to_string(),
+            // 0 is used for the session cookies
+            expires: (expires > 0).then_some(expires as f64),
+            secure: secure.eq_ignore_ascii_case("TRUE"),
+            http_only,
+            same_site: None,
+        });
+    }
+
+    Ok(cookies)
+}
+
+/// Parses a `Cookie` header into session cookies for all the pinterest subdomains
+pub fn parse_cookie_header(cookie_header: &str) -> Vec<SessionCookie> {
+    let cookie_header = cookie_header.trim();
+    let cookie_header = cookie_header.strip_prefix("Cookie:")
+        .or_else(|| cookie_header.strip_prefix("cookie:"))
+        .unwrap_or(cookie_header);
+
+    cookie_header.split(';')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| SessionCookie::new(name.trim(), value.trim()))
+        .filter(|cookie| !cookie.name.is_empty())
+        .collect()
+}
+
+/// Formats the cookies as a Netscape `cookies.txt` file
+///
+/// # Errors
+/// * `InvalidCookies` - If a field of a cookie contains a tab or a line break, it would break the line in fields
+pub fn format_cookies_txt(cookies: &[SessionCookie]) -> crate::Result<String> {
+    let mut cookies_txt = String::from("# Netscape HTTP Cookie File\n");
+    for cookie in cookies {
+        let fields = [&cookie.domain, &cookie.path, &cookie.name, &cookie.value];
+        if fields.iter().any(|field| field.contains(['\t', '\n', '\r'])) {
+            return Err(PinterestError::InvalidCookies(format!("the cookie {:?} can't be written in a cookies.txt file, \
+                                                              it contains a tab or a line break", cookie.name)));
+        }
+        let flag = |value: bool| if value { "TRUE" } else { "FALSE" };
+        cookies_txt.push_str(&format!("{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                                      if cookie.http_only { "#HttpOnly_" } else { "" },
+                                      cookie.domain,
+                                      flag(cookie.domain.starts_with('.')),
+                                      cookie.path,
+                                      flag(cookie.secure),
+                                      cookie.expires.map_or(0, |expires| expires as i64),
+                                      cookie.name,
+                                      cookie.value));
+    }
+    Ok(cookies_txt)
+}
+
+/// Formats the cookies as the value of a `Cookie` header
+pub fn format_cookie_header(cookies: &[SessionCookie]) -> String {
+    cookies.iter()
+        .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Returns true for `pinterest.<tld>` and its subdomains, like `www.pinterest.com`, `.pinterest.fr` or `pinterest.co.uk`
+fn is_pinterest_domain(domain: &str) -> bool {
+    let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+    let labels: Vec<&str> = domain.split('.').collect();
+    let is_country_code = |label: &str| label.len() == 2 && label.bytes().all(|b| b.is_ascii_lowercase());
+
+    let tld_len = match labels[..] {
+        [.., "co" | "com", country] if is_country_code(country) => 2,
+        [.., "com"] => 1,
+        [.., country] if is_country_code(country) => 1,
+        _ => return false,
+    };
+    labels.len() > tld_len && labels[labels.len() - tld_len - 1] == "pinterest"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_pinterest_domain_matches_the_pinterest_tlds() {
+        for domain in ["pinterest.com", ".pinterest.com", "www.pinterest.com", "pinterest.fr", ".pinterest.co.uk",
+                       "pinterest.com.au", "i.PINTEREST.de"] {
+            assert!(is_pinterest_domain(domain), "{domain}");
+        }
+        for domain in ["pinterest.evil.com", "pinterest.com.evil.com", "evilpinterest.com", "pinterest.co.evil",
+                       "pinterest", "example.com", ""] {
+            assert!(!is_pinterest_domain(domain), "{domain}");
+        }
+    }
+
+    #[test]
+    fn a_crf_token_that_is_not_a_header_value_is_rejected() {
+        let result = Pinterest::from_cookie_header("username", "csrftoken=12\u{7}34; _pinterest_sess=abcd");
+        assert!(matches!(result, Err(PinterestError::InvalidCookies(_))));
+    }
+
+    #[test]
+    fn parse_cookies_txt_reads_the_http_only_lines_and_skips_the_comments() {
+        let cookies = parse_cookies_txt("# Netscape HTTP Cookie File\r\n\
+            # https://curl.se/docs/http-cookies.html\r\n\
+            \r\n\
+            #HttpOnly_.pinterest.com\tTRUE\t/\tTRUE\t1893456000\t_pinterest_sess\tabcd\r\n\
+            www.pinterest.com\tFALSE\t/\tFALSE\t0\tcsrftoken\t1234\r\n\
+            pinterest.fr\tTRUE\t/\tFALSE\t0\t_auth\t1\n").unwrap();
+
+        assert_eq!(cookies, [
+            SessionCookie {
+                expires: Some(1_893_456_000.0),
+                secure: true,
+                http_only: true,
+                ..SessionCookie::new("_pinterest_sess", "abcd")
+            },
+            SessionCookie { domain: "www.pinterest.com".to_string(), secure: false, ..SessionCookie::new("csrftoken", "1234") },
+            // The subdomains flag adds the leading dot
+            SessionCookie { domain: ".pinterest.fr".to_string(), secure: false, ..SessionCookie::new("_auth", "1") },
+        ]);
+    }
+
+    #[test]
+    fn parse_cookies_txt_skips_the_other_domains() {
+        let cookies = parse_cookies_txt(".example.com\tTRUE\t/\tFALSE\t0\tcsrftoken\t1\n\
+            #HttpOnly_pinterest.evil.com\tTRUE\t/\tFALSE\t0\t_pinterest_sess\t2\n\
+            .pinterest.com.evil.com\tTRUE\t/\tFALSE\t0\t_auth\t3\n").unwrap();
+        assert!(cookies.is_empty());
+    }
+
+    #[test]
+    fn parse_cookies_txt_keeps_the_expired_cookies_for_the_session_to_drop() {
+        let cookies = parse_cookies_txt(".pinterest.com\tTRUE\t/\tTRUE\t1893456000\tcsrftoken\t1234\n\
+            .pinterest.com\tTRUE\t/\tTRUE\t1000000000\t_pinterest_sess\told\n").unwrap();
+        assert_eq!(cookies[1].expires, Some(1_000_000_000.0));
+        assert!(cookies[1].is_expired());
+
+        let pinterest = Pinterest::from_cookies("username", cookies).unwrap();
+        let names: Vec<_> = pinterest.live_session_cookies().into_iter().map(|cookie| cookie.name).collect();
+        assert_eq!(names, ["csrftoken"]);
+    }
+
+    #[test]
+    fn parse_cookies_txt_reports_the_malformed_line() {
+        for (cookies_txt, line) in [
+            ("# comment\n.pinterest.com\tTRUE\t/\tTRUE\tcsrftoken\t1234\n", "line 2"),
+            (".pinterest.com\tTRUE\t/\tTRUE\tsoon\tcsrftoken\t1234\n", "line 1"),
+            ("\n\n#HttpOnly_.pinterest.com TRUE / TRUE 0 csrftoken 1234\n", "line 3"),
+        ] {
+            match parse_cookies_txt(cookies_txt) {
+                Err(PinterestError::InvalidCookies(error)) => assert!(error.starts_with(line), "{error}"),
+                result => panic!("unexpected result: {result:?}"),
+            }
+        }
+    }
+
+    fn cookie(name: &str, value: &str, expires: Option<f64>, http_only: bool) -> SessionCookie {
+        SessionCookie { expires, http_only, secure: true, ..SessionCookie::new(name, value) }
+    }
+
+    fn session() -> Vec<SessionCookie> {
+        vec![
+            cookie("csrftoken", "1234", Some(4_102_444_800.0), false),
+            cookie("_pinterest_sess", "abcd", Some(4_102_444_800.0), true),
+            cookie("_auth", "1", None, true),
+        ]
+    }
+
+    #[test]
+    fn format_cookies_txt_writes_the_netscape_fields() {
+        let cookies_txt = format_cookies_txt(&session()).unwrap();
+        assert_eq!(cookies_txt, "# Netscape HTTP Cookie File\n\
+            .pinterest.com\tTRUE\t/\tTRUE\t4102444800\tcsrftoken\t1234\n\
+            #HttpOnly_.pinterest.com\tTRUE\t/\tTRUE\t4102444800\t_pinterest_sess\tabcd\n\
+            #HttpOnly_.pinterest.com\tTRUE\t/\tTRUE\t0\t_auth\t1\n");
+
+        let host_only = SessionCookie { domain: "www.pinterest.com".to_string(), secure: false, ..SessionCookie::new("name", "value") };
+        assert!(format_cookies_txt(&[host_only]).unwrap().contains("www.pinterest.com\tFALSE\t/\tFALSE\t0\tname\tvalue\n"));
+    }
+
+    #[test]
+    fn format_cookies_txt_rejects_the_tabs_and_the_line_breaks() {
+        for value in ["a\tb", "a\nb", "a\r\nb"] {
+            let result = format_cookies_txt(&[SessionCookie::new("name", value)]);
+            assert!(matches!(result, Err(PinterestError::InvalidCookies(_))), "{value:?}");
+        }
+        let result = format_cookies_txt(&[SessionCookie::new("na\tme", "value")]);
+        assert!(matches!(result, Err(PinterestError::InvalidCookies(_))));
+    }
+
+    #[test]
+    fn format_cookie_header_joins_the_pairs() {
+        assert_eq!(format_cookie_header(&session()), "csrftoken=1234; _pinterest_sess=abcd; _auth=1");
+        assert_eq!(format_cookie_header(&[]), "");
+        assert_eq!(parse_cookie_header(&format_cookie_header(&session())),
+                   session().into_iter().map(|c| SessionCookie::new(c.name, c.value)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn export_session_writes_every_format() {
+        let mut cookies = session();
+        cookies.push(cookie("expired", "old", Some(1.0), false));
+        let pinterest = Pinterest::from_cookies("username", cookies).unwrap();
+
+        let json: Vec<SessionCookie> = serde_json::from_str(&pinterest.export_session(SessionFormat::Json).unwrap()).unwrap();
+        assert_eq!(json, session());
+        assert_eq!(pinterest.export_session(SessionFormat::CookieHeader).unwrap(), "csrftoken=1234; _pinterest_sess=abcd; _auth=1");
+        assert_eq!(pinterest.export_session(SessionFormat::CookiesTxt).unwrap(), format_cookies_txt(&session()).unwrap());
+    }
+
+    #[test]
+    fn an_exported_cookies_txt_round_trips() {
+        let pinterest = Pinterest::from_cookies("username", session()).unwrap();
+        let path = std::env::temp_dir().join(format!("pinterest_rs_cookies_txt_test_{}.txt", std::process::id()));
+        std::fs::write(&path, pinterest.export_session(SessionFormat::CookiesTxt).unwrap()).unwrap();
+
+        let imported = Pinterest::from_cookies_txt("username", &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(imported.session_cookies(), session());
+        assert_eq!(imported.live_session_cookies(), session());
+    }
+
+    #[test]
+    fn an_exported_cookie_header_round_trips() {
+        let pinterest = Pinterest::from_cookies("username", session()).unwrap();
+        let header = pinterest.export_session(SessionFormat::CookieHeader).unwrap();
+
+        let imported = Pinterest::from_cookie_header("username", &format!("Cookie: {header}")).unwrap();
+        assert_eq!(imported.export_session(SessionFormat::CookieHeader).unwrap(), header);
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::Pinterest;
+
+/// A pinterest user
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct User {
+    /// The user id
+    #[serde(default)]
+    pub id: String,
+    /// The username, used in the profile url
+    pub username: String,
+    /// The display name
+    #[serde(default)]
+    pub full_name: Option<String>,
+    /// The account email, only returned for the logged in user
+    #[serde(default)]
+    pub email: Option<String>,
+    /// The number of followers
+    #[serde(default)]
+    pub follower_count: u64,
+    /// The number of boards
+    #[serde(default)]
+    pub board_count: u64,
+    /// The number of pins
+    #[serde(default)]
+    pub pin_count: u64,
+}
+
+impl Pinterest {
+    /// Gets the logged in user, it's the simplest way to check that the session is still valid
+    ///
+    /// # Errors
+    /// * `Unauthorized` - If the client has no session, or if it expired
+    pub async fn whoami(&self) -> crate::Result<User> {
+        self.get_resource_data("UserSettingsResource", "/settings/", json!({})).await
+    }
+}
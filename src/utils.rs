@@ -1,10 +1,16 @@
 use reqwest::header::{HeaderMap, HeaderValue};
+#[cfg(any(feature = "credentials", feature = "login"))]
+use crate::PinterestError;
 use crate::{DEFAULT_USER_AGENT, PINTEREST_BASE_URL};
 
+/// Builds the headers of the api requests
+///
+/// # Errors
+/// * `InvalidCookies` - If the crf token is not a valid header value, like a pasted cookie with a control character
 #[inline(always)]
 pub(crate) fn build_request_headers(
     #[cfg(any(feature = "credentials", feature = "login"))] crf_token: String,
-    user_agent: Option<String>) -> HeaderMap {
+    user_agent: Option<String>) -> crate::Result<HeaderMap> {
     let mut reqwest_headers: HeaderMap = HeaderMap::with_capacity(
         {
             #[cfg(any(feature = "credentials", feature = "login"))]
@@ -18,12 +24,13 @@ pub(crate) fn build_request_headers(
         HeaderValue::from_str(&user_agent.unwrap_or_else(|| DEFAULT_USER_AGENT.to_string())).unwrap(),
     );
     #[cfg(any(feature = "credentials", feature = "login"))]
-    reqwest_headers.insert("X-CSRFToken", HeaderValue::from_str(&crf_token).unwrap());
+    reqwest_headers.insert("X-CSRFToken", HeaderValue::from_str(&crf_token)
+        .map_err(|_| PinterestError::InvalidCookies("the csrftoken cookie is not a valid header value".to_string()))?);
 
     reqwest_headers.insert("Referer", HeaderValue::from_static(PINTEREST_BASE_URL));
     reqwest_headers.insert("X-Requested-With", HeaderValue::from_static("XMLHttpRequest"));
     reqwest_headers.insert("Accept", HeaderValue::from_static("application/json"));
     reqwest_headers.insert("Content-Type", HeaderValue::from_static("application/x-www-form-urlencoded; charset=UTF-8"));
 
-    reqwest_headers
+    Ok(reqwest_headers)
 }
@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+use std::path::Path;
+use futures::{AsyncWrite, AsyncWriteExt};
+use serde::{Deserialize, Serialize};
+use url::Url;
+#[cfg(feature = "debug")]
+use log::*;
+
+use crate::{fs, Pinterest, PinterestError};
+use crate::pin::Pin;
+
+/// A single video variant of a video pin, as found in the pin's `videos.video_list` object
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoVariant {
+    /// The variant name, like `V_HLSV4`, `V_720P` or `V_EXP7`
+    #[serde(default)]
+    pub name: String,
+    /// The url of the stream (an HLS playlist or an MP4 file)
+    pub url: String,
+    /// The video width in pixels
+    #[serde(default)]
+    pub width: u32,
+    /// The video height in pixels
+    #[serde(default)]
+    pub height: u32,
+    /// The video duration in milliseconds, if known
+    #[serde(default)]
+    pub duration: Option<u64>,
+    /// The thumbnail url of the video, if any
+    #[serde(default)]
+    pub thumbnail: Option<String>,
+}
+
+/// The container format of a video variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VideoFormat {
+    /// An HLS playlist (`.m3u8`), that have to be downloaded segment by segment
+    Hls,
+    /// A plain MP4 file
+    Mp4,
+}
+
+/// How to pick the best video variant, see [`Pinterest::resolve_best_video`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VideoPreference {
+    /// The variant with the most pixels, regardless of the format
+    #[default]
+    HighestResolution,
+    /// The variant with the fewest pixels, regardless of the format
+    LowestResolution,
+    /// The highest resolution variant that is not taller than the given height,
+    /// falls back to the lowest resolution variant if all of them are taller
+    MaxHeight(u32),
+    /// The highest resolution variant of the given format,
+    /// falls back to the highest resolution variant of any format
+    Format(VideoFormat),
+}
+
+impl VideoVariant {
+    /// Returns the container format of the variant, detected from the url or the variant name
+    pub fn format(&self) -> VideoFormat {
+        let path = Url::parse(&self.url)
+            .map(|url| url.path().to_ascii_lowercase())
+            .unwrap_or_else(|_| self.url.to_ascii_lowercase());
+
+        if path.ends_with(".m3u8") || self.name.starts_with("V_HLS") {
+            VideoFormat::Hls
+        } else {
+            VideoFormat::Mp4
+        }
+    }
+
+    /// The number of pixels of a single frame, used to compare the variants
+    #[inline(always)]
+    fn pixels(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+}
+
+/// The video variants of a video pin, deserialized from the pin's `videos.video_list` object
+///
+/// # Example
+/// ```
+/// use pinterest_rs::video::{VideoFormat, VideoList};
+///
+/// let video_list: VideoList = serde_json::from_str(r#"{
+///     "V_HLSV4": { "url": "https://v1.pinimg.com/videos/hls/ab/cd/ef/abcdef.m3u8", "width": 720, "height": 1280 },
+///     "V_720P": { "url": "https://v1.pinimg.com/videos/720p/ab/cd/ef/abcdef.mp4", "width": 720, "height": 1280 }
+/// }"#)?;
+///
+/// assert_eq!(video_list.variants().len(), 2);
+/// assert_eq!(video_list.get("V_720P").unwrap().format(), VideoFormat::Mp4);
+/// # Ok::<(), serde_json::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VideoList(HashMap<String, VideoVariant>);
+
+impl VideoList {
+    /// Returns all the variants, with their `name` set to the variant key, sorted by name
+    pub fn variants(&self) -> Vec<VideoVariant> {
+        let mut variants: Vec<VideoVariant> = self.0.iter()
+            .map(|(name, variant)| VideoVariant { name: name.clone(), ..variant.clone() })
+            .collect();
+        variants.sort_by(|a, b| a.name.cmp(&b.name));
+        variants
+    }
+
+    /// Returns the variant with the given name, like `V_720P`
+    pub fn get(&self, name: &str) -> Option<VideoVariant> {
+        self.0.get(name)
+            .map(|variant| VideoVariant { name: name.to_string(), ..variant.clone() })
+    }
+
+    /// Returns true if there are no variants
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Pinterest {
+    /// Picks the best video variant of a pin according to the given preference,
+    /// returns `None` if the pin is not a video pin
+    ///
+    /// # Arguments
+    /// * `pin` - The video pin, see [`Pin::video_variants`]
+    /// * `preference` - How to pick the best variant, see [`VideoPreference`]
+    pub fn resolve_best_video(&self, pin: &Pin, preference: VideoPreference) -> Option<VideoVariant> {
+        let best = best_variant(pin.video_variants(), preference);
+
+        #[cfg(feature = "debug")]
+        debug!("Resolved the best video variant of the pin {} for {:?}: {:?}", pin.id, preference, best);
+
+        best
+    }
+
+    /// Downloads the given video variant into a single file,
+    /// HLS variants are downloaded with [`Pinterest::download_hls`]
+    ///
+    /// # Arguments
+    /// * `variant` - The video variant to download
+    /// * `path` - The file path to write the video to
+    pub async fn download_video<P: AsRef<Path>>(&self, variant: &VideoVariant, path: P) -> crate::Result<()> {
+        let mut file = fs::create_file(path.as_ref()).await?;
+        self.download_video_to(variant, &mut file).await?;
+        file.close().await?;
+        Ok(())
+    }
+
+    /// Downloads the given video variant into an [`AsyncWrite`], the video is streamed chunk by chunk
+    ///
+    /// # Arguments
+    /// * `variant` - The video variant to download
+    /// * `writer` - Where to write the video
+    pub async fn download_video_to<W: AsyncWrite + Unpin>(&self, variant: &VideoVariant, writer: &mut W) -> crate::Result<()> {
+        match variant.format() {
+            VideoFormat::Hls => self.download_hls_to(&variant.url, writer).await,
+            VideoFormat::Mp4 => {
+                #[cfg(feature = "debug")]
+                info!("Downloading the video: {}", variant.url);
+                fs::write_response(self.send(self.client.get(&variant.url)).await?, writer).await?;
+                writer.flush().await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Downloads an HLS playlist into a single file, by concatenating its segments
+    ///
+    /// If the url points to a master playlist, the variant stream with the highest bandwidth is used.
+    /// MPEG-TS segments produce a `.ts` file, and fragmented MP4 segments (with an `#EXT-X-MAP` init section)
+    /// produce an `.mp4` file, no external tool (like ffmpeg) is required.
+    /// Alternative renditions (like separate audio tracks) are not downloaded.
+    ///
+    /// # Arguments
+    /// * `playlist_url` - The url of the master or media playlist
+    /// * `path` - The file path to write the video to
+    ///
+    /// # Errors
+    /// * `InvalidPlaylist` - If the playlist can't be parsed, or if it's encrypted
+    pub async fn download_hls<P: AsRef<Path>>(&self, playlist_url: &str, path: P) -> crate::Result<()> {
+        let mut file = fs::create_file(path.as_ref()).await?;
+        self.download_hls_to(playlist_url, &mut file).await?;
+        file.close().await?;
+        Ok(())
+    }
+
+    /// Downloads an HLS playlist into an [`AsyncWrite`], see [`Pinterest::download_hls`]
+    ///
+    /// # Arguments
+    /// * `playlist_url` - The url of the master or media playlist
+    /// * `writer` - Where to write the segments, in order
+    ///
+    /// # Errors
+    /// * `InvalidPlaylist` - If the playlist can't be parsed, or if it's encrypted
+    pub async fn download_hls_to<W: AsyncWrite + Unpin>(&self, playlist_url: &str, writer: &mut W) -> crate::Result<()> {
+        let mut playlist_url = parse_url(playlist_url)?;
+        let mut playlist = self.fetch_playlist(&playlist_url).await?;
+
+        if let Some(stream_url) = best_stream(&playlist, &playlist_url)? {
+            #[cfg(feature = "debug")]
+            debug!("Got a master playlist, using the variant stream: {}", stream_url);
+            playlist = self.fetch_playlist(&stream_url).await?;
+            playlist_url = stream_url;
+        }
+
+        let segments = media_segments(&playlist, &playlist_url)?;
+        #[cfg(feature = "debug")]
+        info!("Downloading {} segments", segments.len());
+
+        for segment in segments {
+            #[cfg(feature = "debug")]
+            trace!("Downloading the segment: {}", segment);
+            fs::write_response(self.send(self.client.get(segment)).await?, writer).await?;
+        }
+        writer.flush().await?;
+
+        Ok(())
+    }
+
+    #[inline(always)]
+    async fn fetch_playlist(&self, url: &Url) -> crate::Result<String> {
+        #[cfg(feature = "debug")]
+        debug!("Fetching the playlist: {}", url);
+        Ok(self.send(self.client.get(url.clone())).await?
+            .text().await?)
+    }
+}
+
+/// Picks the best of the variants according to the given preference
+fn best_variant(variants: Vec<VideoVariant>, preference: VideoPreference) -> Option<VideoVariant> {
+    match preference {
+        VideoPreference::HighestResolution => variants.into_iter().max_by_key(VideoVariant::pixels),
+        VideoPreference::LowestResolution => variants.into_iter().min_by_key(VideoVariant::pixels),
+        VideoPreference::MaxHeight(max_height) => {
+            let (fitting, too_tall): (Vec<_>, Vec<_>) = variants.into_iter()
+                .partition(|variant| variant.height <= max_height);
+            fitting.into_iter().max_by_key(VideoVariant::pixels)
+                .or_else(|| too_tall.into_iter().min_by_key(VideoVariant::pixels))
+        }
+        VideoPreference::Format(format) => {
+            let (matching, other): (Vec<_>, Vec<_>) = variants.into_iter()
+                .partition(|variant| variant.format() == format);
+            matching.into_iter().max_by_key(VideoVariant::pixels)
+                .or_else(|| other.into_iter().max_by_key(VideoVariant::pixels))
+        }
+    }
+}
+
+#[inline(always)]
+fn parse_url(url: &str) -> crate::Result<Url> {
+    Url::parse(url).map_err(|e| PinterestError::InvalidPlaylist(format!("invalid url {url}: {e}")))
+}
+
+#[inline(always)]
+fn join_url(base: &Url, uri: &str) -> crate::Result<Url> {
+    base.join(uri).map_err(|e| PinterestError::InvalidPlaylist(format!("invalid uri {uri}: {e}")))
+}
+
+/// Returns the value of an attribute in an HLS tag attribute list, like `BANDWIDTH=1280000,URI="init.mp4"`
+fn tag_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    while !rest.is_empty() {
+        let (key, value) = rest.split_once('=')?;
+        let (value, next) = if let Some(quoted) = value.strip_prefix('"') {
+            let end = quoted.find('"')?;
+            (&quoted[..end], quoted[end + 1..].trim_start_matches(','))
+        } else {
+            value.split_once(',').unwrap_or((value, ""))
+        };
+        if key.trim() == name {
+            return Some(value);
+        }
+        rest = next;
+    }
+    None
+}
+
+/// Returns the url of the highest bandwidth variant stream if this is a master playlist
+fn best_stream(playlist: &str, base: &Url) -> crate::Result<Option<Url>> {
+    let mut lines = playlist.lines().map(str::trim);
+    let mut best: Option<(u64, &str)> = None;
+
+    while let Some(line) = lines.next() {
+        let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") else {
+            continue;
+        };
+        let bandwidth = tag_attribute(attributes, "BANDWIDTH")
+            .and_then(|b| b.parse().ok())
+            .unwrap_or(0);
+        let Some(uri) = lines.by_ref().find(|l| !l.is_empty() && !l.starts_with('#')) else {
+            return Err(PinterestError::InvalidPlaylist("a variant stream has no uri".to_string()));
+        };
+        if best.is_none_or(|(b, _)| bandwidth > b) {
+            best = Some((bandwidth, uri));
+        }
+    }
+
+    best.map(|(_, uri)| join_url(base, uri)).transpose()
+}
+
+/// Returns the urls of the init section (if any) and the segments of a media playlist, in order
+fn media_segments(playlist: &str, base: &Url) -> crate::Result<Vec<Url>> {
+    if !playlist.trim_start().starts_with("#EXTM3U") {
+        return Err(PinterestError::InvalidPlaylist("missing the #EXTM3U header".to_string()));
+    }
+
+    let mut segments = Vec::new();
+    let mut init_section: Option<&str> = None;
+    for line in playlist.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-KEY:") {
+            if tag_attribute(attributes, "METHOD").is_some_and(|method| method != "NONE") {
+                return Err(PinterestError::InvalidPlaylist("encrypted playlists are not supported".to_string()));
+            }
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-MAP:") {
+            let Some(uri) = tag_attribute(attributes, "URI") else {
+                return Err(PinterestError::InvalidPlaylist("#EXT-X-MAP without an uri".to_string()));
+            };
+            // The same init section can be repeated after each discontinuity
+            if init_section != Some(uri) {
+                segments.push(join_url(base, uri)?);
+                init_section = Some(uri);
+            }
+        } else if !line.starts_with('#') {
+            segments.push(join_url(base, line)?);
+        }
+    }
+
+    if segments.is_empty() {
+        return Err(PinterestError::InvalidPlaylist("the playlist has no segments".to_string()));
+    }
+
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> Url {
+        Url::parse("https://v1.pinimg.com/videos/hls/ab/cd/ef/abcdef.m3u8").unwrap()
+    }
+
+    fn variants() -> Vec<VideoVariant> {
+        let video_list: VideoList = serde_json::from_str(r#"{
+            "V_HLSV4": { "url": "https://v1.pinimg.com/videos/hls/ab/cd/ef/abcdef.m3u8", "width": 1080, "height": 1920 },
+            "V_720P": { "url": "https://v1.pinimg.com/videos/720p/ab/cd/ef/abcdef.mp4", "width": 720, "height": 1280 },
+            "V_EXP7": { "url": "https://v1.pinimg.com/videos/exp7/ab/cd/ef/abcdef.mp4", "width": 360, "height": 640 }
+        }"#).unwrap();
+        video_list.variants()
+    }
+
+    fn best_name(preference: VideoPreference) -> Option<String> {
+        best_variant(variants(), preference).map(|variant| variant.name)
+    }
+
+    #[test]
+    fn best_variant_follows_the_preference() {
+        assert_eq!(best_name(VideoPreference::HighestResolution).as_deref(), Some("V_HLSV4"));
+        assert_eq!(best_name(VideoPreference::LowestResolution).as_deref(), Some("V_EXP7"));
+        assert_eq!(best_name(VideoPreference::MaxHeight(1280)).as_deref(), Some("V_720P"));
+        assert_eq!(best_name(VideoPreference::MaxHeight(100)).as_deref(), Some("V_EXP7"));
+        assert_eq!(best_name(VideoPreference::Format(VideoFormat::Mp4)).as_deref(), Some("V_720P"));
+        assert_eq!(best_name(VideoPreference::Format(VideoFormat::Hls)).as_deref(), Some("V_HLSV4"));
+        assert_eq!(best_variant(Vec::new(), VideoPreference::HighestResolution), None);
+    }
+
+    #[test]
+    fn pin_video_variants_are_empty_for_an_image_pin() {
+        let pin: Pin = serde_json::from_str(r#"{ "id": "1", "videos": null }"#).unwrap();
+        assert!(pin.video_variants().is_empty());
+
+        let pin: Pin = serde_json::from_value(serde_json::json!({ "id": "2", "videos": { "video_list": {
+            "V_720P": { "url": "https://v1.pinimg.com/videos/720p/ab/cd/ef/abcdef.mp4", "width": 720, "height": 1280 }
+        } } })).unwrap();
+        assert_eq!(pin.video_variants().len(), 1);
+        assert_eq!(pin.video_variants()[0].name, "V_720P");
+    }
+
+    #[test]
+    fn tag_attribute_reads_quoted_and_plain_values() {
+        let attributes = r#"BANDWIDTH=1280000,CODECS="avc1.4d401f,mp4a.40.2",RESOLUTION=1280x720"#;
+        assert_eq!(tag_attribute(attributes, "BANDWIDTH"), Some("1280000"));
+        assert_eq!(tag_attribute(attributes, "CODECS"), Some("avc1.4d401f,mp4a.40.2"));
+        assert_eq!(tag_attribute(attributes, "RESOLUTION"), Some("1280x720"));
+        assert_eq!(tag_attribute(attributes, "FRAME-RATE"), None);
+        assert_eq!(tag_attribute("", "BANDWIDTH"), None);
+    }
+
+    #[test]
+    fn best_stream_picks_the_highest_bandwidth() {
+        let playlist = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360\n\
+            360p.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2500000,RESOLUTION=1280x720\n\
+            720p.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1400000,RESOLUTION=854x480\n\
+            /videos/hls/ab/cd/ef/480p.m3u8\n";
+        let best = best_stream(playlist, &base()).unwrap().unwrap();
+        assert_eq!(best.as_str(), "https://v1.pinimg.com/videos/hls/ab/cd/ef/720p.m3u8");
+    }
+
+    #[test]
+    fn best_stream_is_none_for_a_media_playlist() {
+        let playlist = "#EXTM3U\n#EXTINF:4.0,\nsegment0.ts\n#EXT-X-ENDLIST\n";
+        assert!(best_stream(playlist, &base()).unwrap().is_none());
+    }
+
+    #[test]
+    fn best_stream_rejects_a_variant_without_uri() {
+        let playlist = "#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=800000\n";
+        assert!(matches!(best_stream(playlist, &base()), Err(PinterestError::InvalidPlaylist(_))));
+    }
+
+    #[test]
+    fn media_segments_resolves_relative_uris() {
+        let playlist = "#EXTM3U\n\
+            #EXT-X-TARGETDURATION:4\n\
+            #EXTINF:4.0,\n\
+            segment0.ts\n\
+            #EXTINF:4.0,\n\
+            ../other/segment1.ts\n\
+            #EXTINF:2.5,\n\
+            https://cdn.example.com/segment2.ts\n\
+            #EXT-X-ENDLIST\n";
+        let segments: Vec<String> = media_segments(playlist, &base()).unwrap().into_iter().map(String::from).collect();
+        assert_eq!(segments, [
+            "https://v1.pinimg.com/videos/hls/ab/cd/ef/segment0.ts",
+            "https://v1.pinimg.com/videos/hls/ab/cd/other/segment1.ts",
+            "https://cdn.example.com/segment2.ts",
+        ]);
+    }
+
+    #[test]
+    fn media_segments_puts_the_init_section_first_once() {
+        let playlist = "#EXTM3U\n\
+            #EXT-X-MAP:URI=\"init.mp4\"\n\
+            #EXTINF:4.0,\n\
+            segment0.m4s\n\
+            #EXT-X-DISCONTINUITY\n\
+            #EXT-X-MAP:URI=\"init.mp4\"\n\
+            #EXTINF:4.0,\n\
+            segment1.m4s\n";
+        let segments: Vec<String> = media_segments(playlist, &base()).unwrap().into_iter().map(String::from).collect();
+        assert_eq!(segments, [
+            "https://v1.pinimg.com/videos/hls/ab/cd/ef/init.mp4",
+            "https://v1.pinimg.com/videos/hls/ab/cd/ef/segment0.m4s",
+            "https://v1.pinimg.com/videos/hls/ab/cd/ef/segment1.m4s",
+        ]);
+    }
+
+    #[test]
+    fn media_segments_rejects_encrypted_playlists() {
+        let playlist = "#EXTM3U\n\
+            #EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\"\n\
+            #EXTINF:4.0,\n\
+            segment0.ts\n";
+        assert!(matches!(media_segments(playlist, &base()), Err(PinterestError::InvalidPlaylist(_))));
+
+        let playlist = "#EXTM3U\n#EXT-X-KEY:METHOD=NONE\n#EXTINF:4.0,\nsegment0.ts\n";
+        assert_eq!(media_segments(playlist, &base()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn media_segments_rejects_invalid_playlists() {
+        assert!(matches!(media_segments("segment0.ts\n", &base()), Err(PinterestError::InvalidPlaylist(_))));
+        assert!(matches!(media_segments("#EXTM3U\n#EXT-X-ENDLIST\n", &base()), Err(PinterestError::InvalidPlaylist(_))));
+        assert!(matches!(media_segments("#EXTM3U\n#EXT-X-MAP:BYTERANGE=\"100@0\"\n", &base()),
+            Err(PinterestError::InvalidPlaylist(_))));
+    }
+}